@@ -0,0 +1,31 @@
+pub mod align;
+pub mod bubbles;
+pub mod clean;
+pub mod coverage;
+pub mod diff;
+pub mod equivalence;
+pub mod kmer;
+pub mod liftover;
+pub mod merge;
+pub mod normalize;
+pub mod orient;
+pub mod prune;
+pub mod search;
+pub mod shortest_path;
+pub mod topological_sort;
+
+pub use self::align::*;
+pub use self::bubbles::*;
+pub use self::clean::*;
+pub use self::coverage::*;
+pub use self::diff::*;
+pub use self::equivalence::*;
+pub use self::kmer::*;
+pub use self::liftover::*;
+pub use self::merge::*;
+pub use self::normalize::*;
+pub use self::orient::*;
+pub use self::prune::*;
+pub use self::search::*;
+pub use self::shortest_path::*;
+pub use self::topological_sort::*;
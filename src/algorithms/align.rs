@@ -0,0 +1,357 @@
+use fnv::FnvHashMap;
+
+use crate::{
+    handle::{Direction, Handle},
+    handlegraph::HandleGraphRef,
+};
+
+use super::topological_sort;
+
+/// Scores used by the aligner; mismatches and gaps are penalties and
+/// are subtracted from the score. A gap of length `n` costs
+/// `gap_open + (n - 1) * gap_extend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scoring {
+    pub match_score: i32,
+    pub mismatch: i32,
+    pub gap_open: i32,
+    pub gap_extend: i32,
+}
+
+impl Default for Scoring {
+    fn default() -> Scoring {
+        Scoring {
+            match_score: 1,
+            mismatch: 4,
+            gap_open: 6,
+            gap_extend: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentMode {
+    /// The whole query against a whole walk from a source to a sink
+    Global,
+    /// The best scoring part of the query against any walk
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    Match,
+    Mismatch,
+    /// Bases of the query missing from the graph
+    Insertion,
+    /// Bases of the graph missing from the query
+    Deletion,
+}
+
+impl CigarOp {
+    pub fn as_char(self) -> char {
+        match self {
+            CigarOp::Match => '=',
+            CigarOp::Mismatch => 'X',
+            CigarOp::Insertion => 'I',
+            CigarOp::Deletion => 'D',
+        }
+    }
+}
+
+/// Formats a CIGAR as a string, e.g. `3=1X2I`
+pub fn cigar_string(cigar: &[(CigarOp, usize)]) -> String {
+    cigar
+        .iter()
+        .map(|(op, len)| format!("{}{}", len, op.as_char()))
+        .collect()
+}
+
+/// The best alignment of a query to a walk of the graph: the query range
+/// `query_start..query_end` is aligned from offset `start` of the first
+/// handle of the walk to right before offset `end` of the last one, with
+/// one CIGAR per handle of the walk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub score: i32,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub walk: Vec<Handle>,
+    pub start: usize,
+    pub end: usize,
+    pub cigars: Vec<Vec<(CigarOp, usize)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Best,
+    Deletion,
+    Insertion,
+}
+
+const NEG_INF: i32 = i32::MIN / 2;
+
+/// A single base of the graph, in topological order
+struct Column {
+    node: usize,
+    offset: usize,
+    base: u8,
+}
+
+/// Function that aligns a query to a directed acyclic graph with affine
+/// gap penalties, in the manner of partial order alignment: the
+/// dynamic programming matrix has a column for each base of the graph,
+/// in topological order, and each column continues from the last base of
+/// every predecessor node.\
+/// Only the forward strand of the nodes is used (see `topological_sort`).
+/// Returns None if the graph is empty or has a cycle, or if no part of
+/// the query aligns with a positive score in local mode.
+/// # Example
+/// ```ignore
+/// let alignment = align_sequence(&graph, b"ACGTTTTACA", &Scoring::default(), AlignmentMode::Global).unwrap();
+/// for (handle, cigar) in alignment.walk.iter().zip(alignment.cigars.iter()) {
+///     // 1: 4=, 3: 4=1D, 4: 2=
+///     println!("{}: {}", handle.id(), cigar_string(cigar));
+/// }
+/// ```
+pub fn align_sequence<G: HandleGraphRef>(
+    graph: G,
+    query: &[u8],
+    scoring: &Scoring,
+    mode: AlignmentMode,
+) -> Option<Alignment> {
+    let order = topological_sort(graph)?;
+    let node_index: FnvHashMap<Handle, usize> =
+        order.iter().enumerate().map(|(i, h)| (*h, i)).collect();
+
+    let mut columns: Vec<Column> = vec![];
+    let mut last_column: Vec<usize> = vec![];
+    for (node, &handle) in order.iter().enumerate() {
+        for (offset, base) in graph.sequence(handle).into_iter().enumerate() {
+            columns.push(Column { node, offset, base });
+        }
+        last_column.push(columns.len() - 1);
+    }
+    if columns.is_empty() {
+        return None;
+    }
+
+    // the virtual column before the start of the graph
+    let start = columns.len();
+    let sinks: Vec<usize> = order
+        .iter()
+        .enumerate()
+        .filter(|(_, &h)| graph.neighbors(h, Direction::Right).all(|n| n.is_reverse()))
+        .map(|(node, _)| last_column[node])
+        .collect();
+
+    let preds: Vec<Vec<usize>> = columns
+        .iter()
+        .enumerate()
+        .map(|(col, column)| {
+            if column.offset > 0 {
+                return vec![col - 1];
+            }
+            let prev: Vec<usize> = graph
+                .neighbors(order[column.node], Direction::Left)
+                .filter_map(|h| node_index.get(&h).map(|&n| last_column[n]))
+                .collect();
+            if prev.is_empty() {
+                vec![start]
+            } else {
+                prev
+            }
+        })
+        .collect();
+
+    let local = mode == AlignmentMode::Local;
+    let rows = query.len() + 1;
+    let substitution = |i: usize, col: usize| {
+        if query[i - 1].eq_ignore_ascii_case(&columns[col].base) {
+            scoring.match_score
+        } else {
+            -scoring.mismatch
+        }
+    };
+
+    let mut best = vec![vec![NEG_INF; start + 1]; rows];
+    let mut deletion = vec![vec![NEG_INF; start + 1]; rows];
+    let mut insertion = vec![vec![NEG_INF; start + 1]; rows];
+
+    best[0][start] = 0;
+    for i in 1..rows {
+        if local {
+            best[i][start] = 0;
+        } else {
+            insertion[i][start] = -scoring.gap_open - (i as i32 - 1) * scoring.gap_extend;
+            best[i][start] = insertion[i][start];
+        }
+    }
+
+    for col in 0..start {
+        for i in 0..rows {
+            let mut del = NEG_INF;
+            let mut sub = NEG_INF;
+            for &p in preds[col].iter() {
+                del = del
+                    .max(best[i][p] - scoring.gap_open)
+                    .max(deletion[i][p] - scoring.gap_extend);
+                if i > 0 {
+                    sub = sub.max(best[i - 1][p] + substitution(i, col));
+                }
+            }
+            let ins = if i > 0 {
+                (best[i - 1][col] - scoring.gap_open)
+                    .max(insertion[i - 1][col] - scoring.gap_extend)
+            } else {
+                NEG_INF
+            };
+
+            deletion[i][col] = del;
+            insertion[i][col] = ins;
+            best[i][col] = sub.max(del).max(ins);
+            if local {
+                best[i][col] = best[i][col].max(0);
+            }
+        }
+    }
+
+    // where the alignment ends
+    let (mut i, mut col) = if local {
+        let (mut end_i, mut end_col) = (0, start);
+        for (r, row) in best.iter().enumerate() {
+            for (c, &score) in row.iter().enumerate().take(start) {
+                if score > best[end_i][end_col] {
+                    end_i = r;
+                    end_col = c;
+                }
+            }
+        }
+        if best[end_i][end_col] <= 0 {
+            return None;
+        }
+        (end_i, end_col)
+    } else {
+        let end_col = *sinks
+            .iter()
+            .max_by_key(|&&c| (best[rows - 1][c], std::cmp::Reverse(c)))?;
+        (rows - 1, end_col)
+    };
+
+    let score = best[i][col];
+    let query_end = i;
+
+    // trace back the operations, with the column of each one; the
+    // insertions before the first base of the graph have no column
+    let mut ops: Vec<(CigarOp, Option<usize>)> = vec![];
+    let mut state = State::Best;
+    while col != start {
+        match state {
+            State::Best => {
+                let value = best[i][col];
+                if local && value == 0 {
+                    break;
+                }
+                let from_sub = if i > 0 {
+                    preds[col]
+                        .iter()
+                        .find(|&&p| best[i - 1][p] + substitution(i, col) == value)
+                } else {
+                    None
+                };
+                if let Some(&p) = from_sub {
+                    let op = if query[i - 1].eq_ignore_ascii_case(&columns[col].base) {
+                        CigarOp::Match
+                    } else {
+                        CigarOp::Mismatch
+                    };
+                    ops.push((op, Some(col)));
+                    i -= 1;
+                    col = p;
+                } else if value == deletion[i][col] {
+                    state = State::Deletion;
+                } else {
+                    state = State::Insertion;
+                }
+            }
+            State::Deletion => {
+                ops.push((CigarOp::Deletion, Some(col)));
+                let value = deletion[i][col];
+                let (p, next) = preds[col]
+                    .iter()
+                    .find_map(|&p| {
+                        if best[i][p] - scoring.gap_open == value {
+                            Some((p, State::Best))
+                        } else if deletion[i][p] - scoring.gap_extend == value {
+                            Some((p, State::Deletion))
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap();
+                col = p;
+                state = next;
+            }
+            State::Insertion => {
+                ops.push((CigarOp::Insertion, Some(col)));
+                let value = insertion[i][col];
+                state = if best[i - 1][col] - scoring.gap_open == value {
+                    State::Best
+                } else {
+                    State::Insertion
+                };
+                i -= 1;
+            }
+        }
+    }
+    // global alignments can start with insertions
+    if !local {
+        ops.extend((0..i).map(|_| (CigarOp::Insertion, None)));
+        i = 0;
+    }
+    ops.reverse();
+
+    let query_start = i;
+    let mut walk: Vec<Handle> = vec![];
+    let mut cigars: Vec<Vec<(CigarOp, usize)>> = vec![];
+    let mut leading = 0;
+    let mut first_col = None;
+    let mut last_col = None;
+
+    for (op, column) in ops {
+        let column = match column {
+            Some(c) => c,
+            None => {
+                leading += 1;
+                continue;
+            }
+        };
+        first_col.get_or_insert(column);
+        last_col = Some(column);
+
+        let handle = order[columns[column].node];
+        if walk.last() != Some(&handle) {
+            walk.push(handle);
+            cigars.push(vec![]);
+        }
+        let cigar = cigars.last_mut().unwrap();
+        if leading > 0 {
+            cigar.push((CigarOp::Insertion, leading));
+            leading = 0;
+        }
+        match cigar.last_mut() {
+            Some((last, len)) if *last == op => *len += 1,
+            _ => cigar.push((op, 1)),
+        }
+    }
+
+    let (first_col, last_col) = (first_col?, last_col?);
+    Some(Alignment {
+        score,
+        query_start,
+        query_end,
+        walk,
+        start: columns[first_col].offset,
+        end: columns[last_col].offset + 1,
+        cigars,
+    })
+}
@@ -0,0 +1,161 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::HandleGraphRef,
+};
+
+/// A default bound for `find_bubbles`: bubbles with more nodes than this
+/// between their boundaries are not reported, and the search from an
+/// entrance stops after visiting this many handles
+pub const MAX_BUBBLE_SIZE: usize = 10_000;
+
+/// A superbubble in the bidirected graph: every walk leaving `start`
+/// reaches `end`, every walk entering `end` comes from `start`, and
+/// the nodes in between form an acyclic subgraph.\
+/// Bubbles are nested, `parent` and `children` are indices into the
+/// vector returned by `find_bubbles`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bubble {
+    pub start: Handle,
+    pub end: Handle,
+    pub contained: Vec<NodeId>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl Bubble {
+    /// Returns true if the node is inside the bubble, boundaries included
+    pub fn contains<T: Into<NodeId>>(&self, node: T) -> bool {
+        let node = node.into();
+        node == self.start.id()
+            || node == self.end.id()
+            || self.contained.binary_search(&node).is_ok()
+    }
+}
+
+/// Looks for the superbubble with the given entrance, following the
+/// algorithm described by Onodera et al. (2013), on the oriented handles
+/// of the graph. Returns the exit and the nodes inside the bubble, giving
+/// up once more than `max_size` handles past the entrance are visited.
+fn superbubble_from<G: HandleGraphRef>(
+    graph: G,
+    start: Handle,
+    max_size: usize,
+) -> Option<(Handle, Vec<NodeId>)> {
+    let mut stack = vec![start];
+    let mut visited: FnvHashSet<Handle> = FnvHashSet::default();
+    let mut seen: FnvHashSet<Handle> = FnvHashSet::default();
+
+    while let Some(handle) = stack.pop() {
+        visited.insert(handle);
+        seen.remove(&handle);
+        if visited.len() > max_size.saturating_add(1) {
+            return None;
+        }
+
+        // a tip, no exit can be found
+        graph.neighbors(handle, Direction::Right).next()?;
+
+        for next in graph.neighbors(handle, Direction::Right) {
+            if next == start {
+                // a cycle through the entrance
+                return None;
+            }
+            seen.insert(next);
+            if graph
+                .neighbors(next, Direction::Left)
+                .all(|prev| visited.contains(&prev))
+            {
+                stack.push(next);
+            }
+        }
+
+        if stack.len() == 1 && seen.len() == 1 && seen.contains(&stack[0]) {
+            let end = stack[0];
+            if graph.has_edge(end, start) {
+                return None;
+            }
+            let mut contained: Vec<NodeId> = visited
+                .iter()
+                .map(|h| h.id())
+                .filter(|&n| n != start.id() && n != end.id())
+                .collect();
+            contained.sort();
+            contained.dedup();
+            return Some((end, contained));
+        }
+    }
+
+    None
+}
+
+/// Function that decomposes a graph into its superbubbles, looking for
+/// a bubble starting at each oriented handle.\
+/// Each search stops after visiting `max_size` handles, so bubbles with
+/// more than `max_size` nodes between their boundaries are not reported,
+/// and the whole decomposition takes at most O(handles * max_size) steps.\
+/// Every bubble is reported once, in the orientation whose boundaries
+/// are smaller, and bubbles with no nodes between the boundaries (i.e.
+/// simple edges) are left out. The bubbles are sorted by their
+/// boundaries, and nested bubbles point to the smallest bubble containing
+/// them.
+/// # Example
+/// ```ignore
+/// // Nodes: 1, 2, 3, 4, 5, 6
+/// // Edges: 1 -> 2, 1 -> 3, 2 -> 4, 3 -> 4, 4 -> 5, 4 -> 6, 5 -> 6
+///
+/// let bubbles = find_bubbles(&graph, MAX_BUBBLE_SIZE);
+///
+/// // Bubble { start: 1+, end: 4+, contained: [2, 3], .. }
+/// // Bubble { start: 4+, end: 6+, contained: [5], .. }
+/// for bubble in bubbles.iter() {
+///     println!("{:?}", bubble);
+/// }
+/// ```
+pub fn find_bubbles<G: HandleGraphRef>(graph: G, max_size: usize) -> Vec<Bubble> {
+    let mut found: FnvHashMap<(Handle, Handle), Vec<NodeId>> = FnvHashMap::default();
+
+    for handle in graph.all_handles() {
+        for start in [handle, handle.flip()].iter() {
+            if let Some((end, contained)) = superbubble_from(graph, *start, max_size) {
+                if contained.is_empty() {
+                    continue;
+                }
+                let key = std::cmp::min((*start, end), (end.flip(), start.flip()));
+                found.entry(key).or_insert(contained);
+            }
+        }
+    }
+
+    let mut bubbles: Vec<Bubble> = found
+        .into_iter()
+        .map(|((start, end), contained)| Bubble {
+            start,
+            end,
+            contained,
+            parent: None,
+            children: vec![],
+        })
+        .collect();
+    bubbles.sort_by_key(|b| (b.start, b.end));
+
+    // superbubbles are either nested or share at most a boundary, so the
+    // parent of a bubble is the smallest one with its entrance inside
+    let mut by_size: Vec<usize> = (0..bubbles.len()).collect();
+    by_size.sort_by_key(|&ix| std::cmp::Reverse(bubbles[ix].contained.len()));
+    let mut innermost: FnvHashMap<NodeId, usize> = FnvHashMap::default();
+    for &ix in by_size.iter() {
+        for &node in bubbles[ix].contained.iter() {
+            innermost.insert(node, ix);
+        }
+    }
+    for child in 0..bubbles.len() {
+        if let Some(&parent) = innermost.get(&bubbles[child].start.id()) {
+            bubbles[child].parent = Some(parent);
+            bubbles[parent].children.push(child);
+        }
+    }
+
+    bubbles
+}
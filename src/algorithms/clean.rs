@@ -0,0 +1,183 @@
+use bstr::BString;
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::{AllEdges, AllHandles, HandleGraphRef, HandleNeighbors, HandleSequences},
+    mutablehandlegraph::SubtractiveHandleGraph,
+    pathgraph::PathHandleGraph,
+};
+
+use super::path_coverage;
+
+/// What a cleaning operation removed, or would remove in a dry run,
+/// sorted: the nodes, the edges (including the ones of the removed
+/// nodes) in the orientation given by `Edge::edge_handle`, and the
+/// paths that crossed any of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanReport {
+    pub nodes: Vec<NodeId>,
+    pub edges: Vec<Edge>,
+    pub paths: Vec<BString>,
+}
+
+impl CleanReport {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty() && self.paths.is_empty()
+    }
+}
+
+/// Removes the nodes and edges, along with the edges of the nodes and
+/// the paths crossing any of them, unless `dry_run` is true
+fn remove_all<G>(
+    graph: &mut G,
+    mut nodes: Vec<NodeId>,
+    edges: Vec<Edge>,
+    dry_run: bool,
+) -> CleanReport
+where
+    G: SubtractiveHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    nodes.sort();
+    nodes.dedup();
+    let node_set: FnvHashSet<NodeId> = nodes.iter().copied().collect();
+
+    let mut edge_set: FnvHashSet<Edge> = edges.into_iter().collect();
+    let graph_ref: &G = graph;
+    for &id in nodes.iter() {
+        let handle = Handle::pack(id, false);
+        for next in graph_ref.neighbors(handle, Direction::Right) {
+            edge_set.insert(Edge::edge_handle(handle, next));
+        }
+        for prev in graph_ref.neighbors(handle, Direction::Left) {
+            edge_set.insert(Edge::edge_handle(prev, handle));
+        }
+    }
+
+    let mut paths: Vec<BString> = vec![];
+    for path in graph_ref.paths_iter() {
+        let handles: Vec<Handle> = graph_ref
+            .steps_iter(path)
+            .filter_map(|step| graph_ref.handle_of_step(&step))
+            .collect();
+        let mut steps = handles.iter().zip(handles.iter().skip(1));
+        let crosses = handles.iter().any(|h| node_set.contains(&h.id()))
+            || steps.any(|(l, r)| edge_set.contains(&Edge::edge_handle(*l, *r)))
+            || (graph_ref.is_circular(path)
+                && handles.len() > 1
+                && edge_set.contains(&Edge::edge_handle(*handles.last().unwrap(), handles[0])));
+        if crosses {
+            paths.push(graph_ref.path_handle_to_name(path).into());
+        }
+    }
+    paths.sort();
+
+    let mut edges: Vec<Edge> = edge_set.into_iter().collect();
+    edges.sort();
+
+    if !dry_run {
+        for name in paths.iter() {
+            graph.remove_path(name);
+        }
+        for edge in edges.iter() {
+            if !node_set.contains(&edge.0.id()) && !node_set.contains(&edge.1.id()) {
+                graph.remove_edge(*edge);
+            }
+        }
+        for &id in nodes.iter() {
+            graph.remove_handle(id);
+        }
+    }
+
+    CleanReport {
+        nodes,
+        edges,
+        paths,
+    }
+}
+
+/// Function that removes the tips shorter than `max_len` bases, i.e.
+/// the nodes with edges on one side only, unless `dry_run` is true.\
+/// Nodes crossed by a path are kept, and a single pass is made, so
+/// nodes that become tips once their neighbors are clipped stay.
+/// # Example
+/// ```ignore
+/// let report = clip_tips(&mut graph, 32, true);
+/// println!("would clip {} tips", report.nodes.len());
+/// ```
+pub fn clip_tips<G>(graph: &mut G, max_len: usize, dry_run: bool) -> CleanReport
+where
+    G: SubtractiveHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let coverage = path_coverage(graph);
+    let graph_ref: &G = graph;
+    let tips: Vec<NodeId> = graph_ref
+        .all_handles()
+        .filter(|&h| {
+            let left = graph_ref.degree(h, Direction::Left);
+            let right = graph_ref.degree(h, Direction::Right);
+            (left == 0) != (right == 0)
+                && graph_ref.node_len(h) < max_len
+                && coverage.node_depth(h.id()).paths == 0
+        })
+        .map(|h| h.id())
+        .collect();
+
+    remove_all(graph, tips, vec![], dry_run)
+}
+
+/// Function that removes the nodes without any edge that no path
+/// crosses, unless `dry_run` is true
+pub fn remove_isolated<G>(graph: &mut G, dry_run: bool) -> CleanReport
+where
+    G: SubtractiveHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let coverage = path_coverage(graph);
+    let graph_ref: &G = graph;
+    let isolated: Vec<NodeId> = graph_ref
+        .all_handles()
+        .filter(|&h| {
+            graph_ref.degree(h, Direction::Left) == 0
+                && graph_ref.degree(h, Direction::Right) == 0
+                && coverage.node_depth(h.id()).paths == 0
+        })
+        .map(|h| h.id())
+        .collect();
+
+    remove_all(graph, isolated, vec![], dry_run)
+}
+
+/// Function that removes the nodes and edges crossed by fewer than
+/// `min_paths` distinct paths, unless `dry_run` is true.\
+/// Paths aren't broken around what is removed: a path crossing a removed
+/// node or edge is removed as a whole, and listed in the report. With
+/// `min_paths` above 1 this drops every path with a node or an edge of
+/// its own.
+/// # Example
+/// ```ignore
+/// // drop everything supported by a single haplotype
+/// let report = prune_low_coverage(&mut graph, 2, false);
+/// ```
+pub fn prune_low_coverage<G>(graph: &mut G, min_paths: usize, dry_run: bool) -> CleanReport
+where
+    G: SubtractiveHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let coverage = path_coverage(graph);
+    let graph_ref: &G = graph;
+    let nodes: Vec<NodeId> = graph_ref
+        .all_handles()
+        .map(|h| h.id())
+        .filter(|&id| coverage.node_depth(id).paths < min_paths)
+        .collect();
+    let edges: Vec<Edge> = graph_ref
+        .all_edges()
+        .filter(|&Edge(l, r)| coverage.edge_depth(l, r).paths < min_paths)
+        .map(|Edge(l, r)| Edge::edge_handle(l, r))
+        .collect();
+
+    remove_all(graph, nodes, edges, dry_run)
+}
@@ -0,0 +1,194 @@
+use bstr::BString;
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::{AllHandles, HandleGraphRef},
+    pathgraph::PathHandleGraph,
+};
+
+/// How many paths cross a node or an edge, and how many times they
+/// cross it in total
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Depth {
+    pub paths: usize,
+    pub visits: usize,
+}
+
+/// The depth of every node and of every edge crossed by a path. Edges
+/// are stored in the canonical orientation given by `Edge::edge_handle`.
+#[derive(Debug, Default, Clone)]
+pub struct Coverage {
+    pub nodes: FnvHashMap<NodeId, Depth>,
+    pub edges: FnvHashMap<Edge, Depth>,
+}
+
+impl Coverage {
+    pub fn node_depth<T: Into<NodeId>>(&self, node: T) -> Depth {
+        self.nodes.get(&node.into()).copied().unwrap_or_default()
+    }
+
+    pub fn edge_depth(&self, left: Handle, right: Handle) -> Depth {
+        let edge = Edge::edge_handle(left, right);
+        self.edges.get(&edge).copied().unwrap_or_default()
+    }
+}
+
+/// Function that computes the depth of every node and edge of the graph.
+/// Nodes not crossed by any path have depth 0, and the step from the
+/// last to the first node of circular paths counts as an edge.
+/// # Example
+/// ```ignore
+/// let coverage = path_coverage(&graph);
+/// // Depth { paths: 3, visits: 4 }
+/// println!("{:?}", coverage.node_depth(12));
+/// ```
+pub fn path_coverage<G>(graph: &G) -> Coverage
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let mut coverage = Coverage::default();
+    for handle in graph.all_handles() {
+        coverage.nodes.insert(handle.id(), Depth::default());
+    }
+
+    for path in graph.paths_iter() {
+        let handles: Vec<Handle> = graph
+            .steps_iter(path)
+            .filter_map(|step| graph.handle_of_step(&step))
+            .collect();
+
+        let mut edges: Vec<Edge> = handles
+            .iter()
+            .zip(handles.iter().skip(1))
+            .map(|(l, r)| Edge::edge_handle(*l, *r))
+            .collect();
+        if graph.is_circular(path) && !handles.is_empty() {
+            edges.push(Edge::edge_handle(*handles.last().unwrap(), handles[0]));
+        }
+
+        let mut seen_nodes: FnvHashSet<NodeId> = FnvHashSet::default();
+        for handle in handles.iter() {
+            let depth = coverage.nodes.entry(handle.id()).or_default();
+            depth.visits += 1;
+            if seen_nodes.insert(handle.id()) {
+                depth.paths += 1;
+            }
+        }
+
+        let mut seen_edges: FnvHashSet<Edge> = FnvHashSet::default();
+        for edge in edges {
+            let depth = coverage.edges.entry(edge).or_default();
+            depth.visits += 1;
+            if seen_edges.insert(edge) {
+                depth.paths += 1;
+            }
+        }
+    }
+
+    coverage
+}
+
+/// Returns the sample part of a path name following the PanSN
+/// convention (`sample#haplotype#contig`), or the whole name if it
+/// doesn't contain a `#`
+pub fn pansn_sample(name: &[u8]) -> &[u8] {
+    name.split(|&c| c == b'#').next().unwrap_or(name)
+}
+
+/// Presence or absence of every node in each group of paths: `presence[i][j]`
+/// is true if a path of `groups[j]` crosses `nodes[i]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresenceMatrix {
+    pub groups: Vec<BString>,
+    pub nodes: Vec<NodeId>,
+    pub presence: Vec<Vec<bool>>,
+}
+
+impl PresenceMatrix {
+    /// Number of groups the node at the given row is present in
+    pub fn group_count(&self, row: usize) -> usize {
+        self.presence[row].iter().filter(|&&p| p).count()
+    }
+}
+
+/// Function that builds the presence/absence matrix of the nodes in the
+/// groups of paths given by `group_of`, which maps a path name to the
+/// name of its group. Nodes and groups are sorted.
+/// # Example
+/// ```ignore
+/// // one column per sample: HG002#1#chr1 and HG002#2#chr1 are grouped together
+/// let matrix = presence_matrix(&graph, |name| pansn_sample(name).into());
+/// ```
+pub fn presence_matrix<G, F>(graph: &G, group_of: F) -> PresenceMatrix
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+    F: Fn(&[u8]) -> BString,
+{
+    let mut nodes: Vec<NodeId> = graph.all_handles().map(|h| h.id()).collect();
+    nodes.sort();
+    let rows: FnvHashMap<NodeId, usize> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+    let mut path_groups: Vec<(BString, &G::PathHandle)> = graph
+        .paths_iter()
+        .map(|path| (group_of(graph.path_handle_to_name(path)), path))
+        .collect();
+    path_groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut groups: Vec<BString> = path_groups.iter().map(|(g, _)| g.clone()).collect();
+    groups.dedup();
+
+    let mut presence = vec![vec![false; groups.len()]; nodes.len()];
+    for (group, path) in path_groups {
+        let column = groups.binary_search(&group).unwrap();
+        for step in graph.steps_iter(path) {
+            if let Some(row) = graph.handle_of_step(&step).and_then(|h| rows.get(&h.id())) {
+                presence[*row][column] = true;
+            }
+        }
+    }
+
+    PresenceMatrix {
+        groups,
+        nodes,
+        presence,
+    }
+}
+
+/// Node counts and total bases of the core (present in every group),
+/// shell (present in more than one group but not all), cloud (present in
+/// a single group) and uncovered parts of the graph
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageSummary {
+    pub core_nodes: usize,
+    pub core_bases: usize,
+    pub shell_nodes: usize,
+    pub shell_bases: usize,
+    pub cloud_nodes: usize,
+    pub cloud_bases: usize,
+    pub uncovered_nodes: usize,
+    pub uncovered_bases: usize,
+}
+
+/// Function that classifies the nodes of a presence/absence matrix in
+/// core, shell and cloud nodes
+pub fn coverage_summary<G: HandleGraphRef>(graph: G, matrix: &PresenceMatrix) -> CoverageSummary {
+    let mut summary = CoverageSummary::default();
+    let total = matrix.groups.len();
+
+    for (row, node) in matrix.nodes.iter().enumerate() {
+        let bases = graph.node_len(Handle::pack(*node, false));
+        let (nodes, total_bases) = match matrix.group_count(row) {
+            0 => (&mut summary.uncovered_nodes, &mut summary.uncovered_bases),
+            n if n == total => (&mut summary.core_nodes, &mut summary.core_bases),
+            1 => (&mut summary.cloud_nodes, &mut summary.cloud_bases),
+            _ => (&mut summary.shell_nodes, &mut summary.shell_bases),
+        };
+        *nodes += 1;
+        *total_bases += bases;
+    }
+
+    summary
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use bstr::BString;
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::HandleGraphRef,
+    pathgraph::PathHandleGraph,
+};
+
+/// A path present in both graphs whose steps, or the sequence they
+/// spell, differ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDiff {
+    pub name: BString,
+    pub steps_differ: bool,
+    pub sequence_differs: bool,
+}
+
+/// The differences between two graphs, sorted by id, edge or name.
+/// Nodes are matched by id, and a node with the same id but a
+/// different sequence is reported as changed, as `(id, old, new)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<(NodeId, BString)>,
+    pub removed_nodes: Vec<(NodeId, BString)>,
+    pub changed_nodes: Vec<(NodeId, BString, BString)>,
+    pub added_edges: Vec<Edge>,
+    pub removed_edges: Vec<Edge>,
+    pub added_paths: Vec<BString>,
+    pub removed_paths: Vec<BString>,
+    pub changed_paths: Vec<PathDiff>,
+}
+
+impl GraphDiff {
+    /// Returns true if the two graphs are identical
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.added_paths.is_empty()
+            && self.removed_paths.is_empty()
+            && self.changed_paths.is_empty()
+    }
+}
+
+fn node_sequences<G>(graph: G) -> BTreeMap<NodeId, BString>
+where
+    G: HandleGraphRef,
+{
+    graph
+        .all_handles()
+        .map(|h| (h.id(), graph.sequence(h.forward()).into()))
+        .collect()
+}
+
+fn edges<G: HandleGraphRef>(graph: G) -> FnvHashSet<Edge> {
+    graph
+        .all_edges()
+        .map(|Edge(l, r)| Edge::edge_handle(l, r))
+        .collect()
+}
+
+/// The steps of each path, by name
+pub(crate) fn path_steps<G: PathHandleGraph>(graph: &G) -> BTreeMap<BString, Vec<Handle>> {
+    graph
+        .paths_iter()
+        .map(|path| {
+            let steps = graph
+                .steps_iter(path)
+                .filter_map(|step| graph.handle_of_step(&step))
+                .collect();
+            (graph.path_handle_to_name(path).into(), steps)
+        })
+        .collect()
+}
+
+pub(crate) fn spell<G: HandleGraphRef>(graph: G, steps: &[Handle]) -> Vec<u8> {
+    steps.iter().flat_map(|&h| graph.sequence(h)).collect()
+}
+
+/// Function that compares two graphs, reporting the nodes, edges and
+/// paths that were added, removed or changed going from `old` to `new`.
+/// Edges are compared regardless of the orientation they are stored in.
+/// # Example
+/// ```ignore
+/// let diff = graph_diff(&before, &after);
+/// if !diff.is_empty() {
+///     write_graph_diff(std::io::stdout(), &diff)?;
+/// }
+/// ```
+pub fn graph_diff<A, B>(old: &A, new: &B) -> GraphDiff
+where
+    A: PathHandleGraph,
+    for<'a> &'a A: HandleGraphRef,
+    B: PathHandleGraph,
+    for<'a> &'a B: HandleGraphRef,
+{
+    let mut diff = GraphDiff::default();
+
+    let old_nodes = node_sequences(old);
+    let new_nodes = node_sequences(new);
+    for (id, seq) in old_nodes.iter() {
+        match new_nodes.get(id) {
+            None => diff.removed_nodes.push((*id, seq.clone())),
+            Some(new_seq) if new_seq != seq => {
+                diff.changed_nodes.push((*id, seq.clone(), new_seq.clone()))
+            }
+            _ => (),
+        }
+    }
+    for (id, seq) in new_nodes.iter() {
+        if !old_nodes.contains_key(id) {
+            diff.added_nodes.push((*id, seq.clone()));
+        }
+    }
+
+    let old_edges = edges(old);
+    let new_edges = edges(new);
+    diff.removed_edges = old_edges.difference(&new_edges).copied().collect();
+    diff.added_edges = new_edges.difference(&old_edges).copied().collect();
+    diff.removed_edges.sort();
+    diff.added_edges.sort();
+
+    let old_paths = path_steps(old);
+    let new_paths = path_steps(new);
+    for (name, steps) in old_paths.iter() {
+        match new_paths.get(name) {
+            None => diff.removed_paths.push(name.clone()),
+            Some(new_steps) => {
+                let steps_differ = steps != new_steps;
+                let sequence_differs = spell(old, steps) != spell(new, new_steps);
+                if steps_differ || sequence_differs {
+                    diff.changed_paths.push(PathDiff {
+                        name: name.clone(),
+                        steps_differ,
+                        sequence_differs,
+                    });
+                }
+            }
+        }
+    }
+    diff.added_paths = new_paths
+        .keys()
+        .filter(|name| !old_paths.contains_key(*name))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+fn orientation(handle: Handle) -> char {
+    if handle.is_reverse() {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+/// Function that writes a diff as tab separated lines, one per change:
+/// the kind of element (`node`, `edge` or `path`), the change (`+` for
+/// added, `-` for removed, `~` for changed), then the id and sequence(s)
+/// of a node, the two oriented handles of an edge, or the name of a
+/// path followed, for changed paths, by `steps`, `sequence` or both.
+/// # Example
+/// ```ignore
+/// // node    ~   4   ACGT    ACCT
+/// // edge    +   3+  5-
+/// // path    ~   HG002#1#chr1    steps,sequence
+/// write_graph_diff(std::io::stdout(), &diff)?;
+/// ```
+pub fn write_graph_diff<W: Write>(mut writer: W, diff: &GraphDiff) -> io::Result<()> {
+    for (id, seq) in diff.added_nodes.iter() {
+        writeln!(writer, "node\t+\t{}\t{}", id, seq)?;
+    }
+    for (id, seq) in diff.removed_nodes.iter() {
+        writeln!(writer, "node\t-\t{}\t{}", id, seq)?;
+    }
+    for (id, old, new) in diff.changed_nodes.iter() {
+        writeln!(writer, "node\t~\t{}\t{}\t{}", id, old, new)?;
+    }
+
+    let edge_lines = diff
+        .added_edges
+        .iter()
+        .map(|e| ('+', e))
+        .chain(diff.removed_edges.iter().map(|e| ('-', e)));
+    for (change, Edge(l, r)) in edge_lines {
+        writeln!(
+            writer,
+            "edge\t{}\t{}{}\t{}{}",
+            change,
+            l.id(),
+            orientation(*l),
+            r.id(),
+            orientation(*r)
+        )?;
+    }
+
+    for name in diff.added_paths.iter() {
+        writeln!(writer, "path\t+\t{}", name)?;
+    }
+    for name in diff.removed_paths.iter() {
+        writeln!(writer, "path\t-\t{}", name)?;
+    }
+    for path in diff.changed_paths.iter() {
+        let what = match (path.steps_differ, path.sequence_differs) {
+            (true, true) => "steps,sequence",
+            (true, false) => "steps",
+            _ => "sequence",
+        };
+        writeln!(writer, "path\t~\t{}\t{}", path.name, what)?;
+    }
+
+    Ok(())
+}
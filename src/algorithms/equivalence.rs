@@ -0,0 +1,466 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use bio::alphabets::dna;
+use bstr::BString;
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::HandleGraphRef,
+    pathgraph::PathHandleGraph,
+};
+
+use super::diff::{path_steps, spell};
+
+/// The first difference found between two graphs that are not
+/// equivalent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EquivalenceError {
+    /// The graphs don't have the same sequences connected in the same way
+    Structure,
+    /// The path is only in one of the graphs
+    PathMissing(BString),
+    /// The path spells different sequences in the two graphs
+    PathSequence(BString),
+    /// The path spells the same sequence along a different route
+    PathRoute(BString),
+}
+
+/// The segment, offset in the segment and strand of each base of a walk
+type Route = Vec<(usize, usize, bool)>;
+
+/// A graph with its unbranched chains of nodes merged into segments,
+/// which don't depend on where the nodes were split. Segments are
+/// oriented like handles, with the index of the segment as id.
+struct Compacted {
+    sequences: Vec<Vec<u8>>,
+    edges: FnvHashSet<Edge>,
+    /// The canonical edges touching each segment
+    incident: Vec<Vec<Edge>>,
+    /// The segment of each node, the offset of the node in the segment,
+    /// and whether the node is reversed in it
+    place: FnvHashMap<NodeId, (usize, usize, bool)>,
+}
+
+fn segment(index: usize, is_reverse: bool) -> Handle {
+    Handle::pack(index as u64, is_reverse)
+}
+
+fn segment_index(segment: Handle) -> usize {
+    u64::from(segment.id()) as usize
+}
+
+impl Compacted {
+    fn new<G: HandleGraphRef>(graph: G) -> Compacted {
+        let mut ids: Vec<NodeId> = graph.all_handles().map(|h| h.id()).collect();
+        ids.sort();
+
+        let only_neighbor = |h: Handle, dir: Direction| {
+            let mut neighbors = graph.neighbors(h, dir);
+            let first = neighbors.next()?;
+            match neighbors.next() {
+                Some(_) => None,
+                None => Some(first),
+            }
+        };
+        // the handle that continues the chain right after the handle
+        let follows = |h: Handle| {
+            let next = only_neighbor(h, Direction::Right)?;
+            if next.id() != h.id() && only_neighbor(next, Direction::Left)? == h {
+                Some(next)
+            } else {
+                None
+            }
+        };
+
+        let mut walks: Vec<VecDeque<Handle>> = vec![];
+        let mut place = FnvHashMap::default();
+        for id in ids {
+            if place.contains_key(&id) {
+                continue;
+            }
+            let start = Handle::pack(id, false);
+            let mut seen: FnvHashSet<NodeId> = FnvHashSet::default();
+            seen.insert(id);
+            let mut walk = VecDeque::new();
+            walk.push_back(start);
+
+            let mut h = start;
+            while let Some(prev) = follows(h.flip()).map(|p| p.flip()) {
+                if !seen.insert(prev.id()) {
+                    break;
+                }
+                walk.push_front(prev);
+                h = prev;
+            }
+            let mut h = start;
+            while let Some(next) = follows(h) {
+                if !seen.insert(next.id()) {
+                    break;
+                }
+                walk.push_back(next);
+                h = next;
+            }
+
+            let mut offset = 0;
+            for &h in walk.iter() {
+                place.insert(h.id(), (walks.len(), offset, h.is_reverse()));
+                offset += graph.node_len(h);
+            }
+            walks.push(walk);
+        }
+
+        // the segment handle entering the segment through the handle
+        let entering = |h: Handle| {
+            let (s, _, reversed) = place[&h.id()];
+            segment(s, h.is_reverse() != reversed)
+        };
+
+        let mut edges = FnvHashSet::default();
+        let mut incident: Vec<Vec<Edge>> = vec![vec![]; walks.len()];
+        for (s, walk) in walks.iter().enumerate() {
+            let first = *walk.front().unwrap();
+            let last = *walk.back().unwrap();
+            let right = graph
+                .neighbors(last, Direction::Right)
+                .map(|n| Edge::edge_handle(segment(s, false), entering(n)));
+            let left = graph
+                .neighbors(first, Direction::Left)
+                .map(|p| Edge::edge_handle(entering(p.flip()).flip(), segment(s, false)));
+            for edge in right.chain(left) {
+                if edges.insert(edge) {
+                    incident[segment_index(edge.0)].push(edge);
+                    if edge.0.id() != edge.1.id() {
+                        incident[segment_index(edge.1)].push(edge);
+                    }
+                }
+            }
+        }
+
+        let sequences = walks
+            .iter()
+            .map(|walk| walk.iter().flat_map(|&h| graph.sequence(h)).collect())
+            .collect();
+
+        Compacted {
+            sequences,
+            edges,
+            incident,
+            place,
+        }
+    }
+
+    fn route<G: HandleGraphRef>(&self, graph: G, walk: &[Handle]) -> Route {
+        let mut route = vec![];
+        for &h in walk {
+            let (s, offset, reversed) = self.place[&h.id()];
+            let len = graph.node_len(h);
+            let is_reverse = h.is_reverse() != reversed;
+            for i in 0..len {
+                let i = if is_reverse { len - 1 - i } else { i };
+                route.push((s, offset + i, is_reverse));
+            }
+        }
+        route
+    }
+}
+
+/// Colors of the segments of both graphs, refined from their sequence
+/// (in either orientation) and the paths crossing them until they stop
+/// splitting; segments can only be matched if they have the same color
+fn refine_colors(
+    a: &Compacted,
+    b: &Compacted,
+    a_paths: &[BTreeSet<BString>],
+    b_paths: &[BTreeSet<BString>],
+) -> (Vec<usize>, Vec<usize>) {
+    let mut labels: FnvHashMap<(Vec<u8>, BTreeSet<BString>), usize> = FnvHashMap::default();
+    let mut initial = |graph: &Compacted, paths: &[BTreeSet<BString>]| -> Vec<usize> {
+        graph
+            .sequences
+            .iter()
+            .zip(paths.iter())
+            .map(|(seq, paths)| {
+                let rev = dna::revcomp(seq.as_slice());
+                let canonical = seq.clone().min(rev);
+                let n = labels.len();
+                *labels.entry((canonical, paths.clone())).or_insert(n)
+            })
+            .collect()
+    };
+    let mut a_colors = initial(a, a_paths);
+    let mut b_colors = initial(b, b_paths);
+
+    let count = |colors: &[usize]| colors.iter().collect::<FnvHashSet<_>>().len();
+    loop {
+        let mut signatures: FnvHashMap<(usize, Vec<usize>), usize> = FnvHashMap::default();
+        let mut refine = |graph: &Compacted, colors: &[usize]| -> Vec<usize> {
+            (0..colors.len())
+                .map(|s| {
+                    let mut neighbors: Vec<usize> = graph.incident[s]
+                        .iter()
+                        .flat_map(|e| vec![e.0, e.1])
+                        .map(|n| colors[segment_index(n)])
+                        .collect();
+                    neighbors.sort_unstable();
+                    let n = signatures.len();
+                    *signatures.entry((colors[s], neighbors)).or_insert(n)
+                })
+                .collect()
+        };
+        let new_a = refine(a, &a_colors);
+        let new_b = refine(b, &b_colors);
+        let done = count(&new_a) == count(&a_colors) && count(&new_b) == count(&b_colors);
+        a_colors = new_a;
+        b_colors = new_b;
+        if done {
+            return (a_colors, b_colors);
+        }
+    }
+}
+
+/// Maps a segment handle of the first graph through the partial mapping
+fn map_segment(mapping: &[Option<Handle>], s: Handle) -> Option<Handle> {
+    let mapped = mapping[segment_index(s)]?;
+    Some(if s.is_reverse() {
+        mapped.flip()
+    } else {
+        mapped
+    })
+}
+
+/// Looks for a mapping of the segments of `a` to the oriented segments
+/// of `b` that preserves their sequences and edges, backtracking over
+/// the segments with the same color, and returns the first one accepted
+/// by `accept`
+fn find_mapping<F>(
+    a: &Compacted,
+    b: &Compacted,
+    a_colors: &[usize],
+    b_colors: &[usize],
+    mut accept: F,
+) -> Option<Vec<Option<Handle>>>
+where
+    F: FnMut(&[Option<Handle>]) -> bool,
+{
+    let mut classes: FnvHashMap<usize, Vec<usize>> = FnvHashMap::default();
+    for (s, &color) in b_colors.iter().enumerate() {
+        classes.entry(color).or_default().push(s);
+    }
+
+    // breadth first from the segments with the fewest candidates, so
+    // that most segments are checked against mapped neighbors
+    let mut starts: Vec<usize> = (0..a.sequences.len()).collect();
+    starts.sort_by_key(|&s| (classes.get(&a_colors[s]).map_or(0, |c| c.len()), s));
+    let mut order = vec![];
+    let mut queued = vec![false; a.sequences.len()];
+    for start in starts {
+        if queued[start] {
+            continue;
+        }
+        queued[start] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(s) = queue.pop_front() {
+            order.push(s);
+            for e in a.incident[s].iter() {
+                for n in [segment_index(e.0), segment_index(e.1)].iter() {
+                    if !queued[*n] {
+                        queued[*n] = true;
+                        queue.push_back(*n);
+                    }
+                }
+            }
+        }
+    }
+
+    let candidates = |s: usize| -> Vec<Handle> {
+        let seq = &a.sequences[s];
+        let mut found = vec![];
+        for &t in classes.get(&a_colors[s]).into_iter().flatten() {
+            if b.sequences[t] == *seq {
+                found.push(segment(t, false));
+            }
+            if dna::revcomp(b.sequences[t].as_slice()) == *seq {
+                found.push(segment(t, true));
+            }
+        }
+        found
+    };
+
+    let mut mapping: Vec<Option<Handle>> = vec![None; a.sequences.len()];
+    let mut used = vec![false; b.sequences.len()];
+    let consistent = |mapping: &[Option<Handle>], s: usize| {
+        a.incident[s].iter().all(
+            |e| match (map_segment(mapping, e.0), map_segment(mapping, e.1)) {
+                (Some(l), Some(r)) => b.edges.contains(&Edge::edge_handle(l, r)),
+                _ => true,
+            },
+        )
+    };
+
+    // iterative backtracking, with the candidates of each level and the
+    // next one to try
+    let mut stack: Vec<(Vec<Handle>, usize)> = vec![];
+    if let Some(&first) = order.first() {
+        stack.push((candidates(first), 0));
+    } else if accept(&mapping) {
+        return Some(mapping);
+    }
+    while !stack.is_empty() {
+        let s = order[stack.len() - 1];
+        let (options, next) = stack.last_mut().unwrap();
+        if let Some(previous) = mapping[s].take() {
+            used[segment_index(previous)] = false;
+        }
+
+        let mut placed = false;
+        while *next < options.len() {
+            let option = options[*next];
+            *next += 1;
+            if used[segment_index(option)] {
+                continue;
+            }
+            mapping[s] = Some(option);
+            if consistent(&mapping, s) {
+                used[segment_index(option)] = true;
+                placed = true;
+                break;
+            }
+            mapping[s] = None;
+        }
+
+        if !placed {
+            stack.pop();
+        } else if stack.len() == order.len() {
+            if accept(&mapping) {
+                return Some(mapping);
+            }
+        } else {
+            let s = order[stack.len()];
+            stack.push((candidates(s), 0));
+        }
+    }
+
+    None
+}
+
+/// Function that checks whether two graphs encode the same sequence
+/// graph with the same paths, regardless of node ids and of where
+/// sequences are split into nodes: unbranched chains of nodes are
+/// merged, the merged graphs must be identical up to renumbering and
+/// orientation, and every path must spell the same sequence along the
+/// same route in both.\
+/// A connected component made of a single unbranched cycle is cut at
+/// its smallest node, so its two versions are only found equivalent if
+/// they are cut at the same base.
+/// # Example
+/// ```ignore
+/// let mut chopped = graph.clone();
+/// chopped.divide_handle(handle, vec![3, 7]);
+///
+/// assert_eq!(check_equivalence(&graph, &chopped), Ok(()));
+/// ```
+pub fn check_equivalence<A, B>(a: &A, b: &B) -> Result<(), EquivalenceError>
+where
+    A: PathHandleGraph,
+    for<'a> &'a A: HandleGraphRef,
+    B: PathHandleGraph,
+    for<'a> &'a B: HandleGraphRef,
+{
+    let a_steps = path_steps(a);
+    let b_steps = path_steps(b);
+    let missing = a_steps
+        .keys()
+        .find(|name| !b_steps.contains_key(*name))
+        .or_else(|| b_steps.keys().find(|name| !a_steps.contains_key(*name)));
+    if let Some(name) = missing {
+        return Err(EquivalenceError::PathMissing(name.clone()));
+    }
+    for (name, steps) in a_steps.iter() {
+        if spell(a, steps) != spell(b, &b_steps[name]) {
+            return Err(EquivalenceError::PathSequence(name.clone()));
+        }
+    }
+
+    let a_graph = Compacted::new(a);
+    let b_graph = Compacted::new(b);
+    if a_graph.sequences.len() != b_graph.sequences.len()
+        || a_graph.edges.len() != b_graph.edges.len()
+    {
+        return Err(EquivalenceError::Structure);
+    }
+
+    let paths_on = |graph: &Compacted, routes: &[(BString, Route)]| {
+        let mut paths = vec![BTreeSet::new(); graph.sequences.len()];
+        for (name, route) in routes.iter() {
+            for &(s, _, _) in route.iter() {
+                paths[s].insert(name.clone());
+            }
+        }
+        paths
+    };
+    let a_routes: Vec<(BString, Route)> = a_steps
+        .iter()
+        .map(|(name, walk)| (name.clone(), a_graph.route(a, walk)))
+        .collect();
+    let b_routes: Vec<(BString, Route)> = b_steps
+        .iter()
+        .map(|(name, walk)| (name.clone(), b_graph.route(b, walk)))
+        .collect();
+    let a_paths = paths_on(&a_graph, &a_routes);
+    let b_paths = paths_on(&b_graph, &b_routes);
+    let (a_colors, b_colors) = refine_colors(&a_graph, &b_graph, &a_paths, &b_paths);
+
+    // the first path whose route doesn't map to its route in `b`
+    let wrong_route = |mapping: &[Option<Handle>]| {
+        a_routes
+            .iter()
+            .zip(b_routes.iter())
+            .find(|((_, a_route), (_, b_route))| {
+                a_route.len() != b_route.len()
+                    || a_route
+                        .iter()
+                        .zip(b_route.iter())
+                        .any(|(&(s, offset, rev), &expected)| {
+                            let mapped = mapping[s].unwrap();
+                            let t = segment_index(mapped);
+                            let base = if mapped.is_reverse() {
+                                (t, b_graph.sequences[t].len() - 1 - offset, !rev)
+                            } else {
+                                (t, offset, rev)
+                            };
+                            base != expected
+                        })
+            })
+            .map(|((name, _), _)| name.clone())
+    };
+
+    let found = find_mapping(&a_graph, &b_graph, &a_colors, &b_colors, |mapping| {
+        wrong_route(mapping).is_none()
+    });
+    if found.is_some() {
+        return Ok(());
+    }
+
+    // tell a different structure from paths taking different routes
+    let no_paths = vec![BTreeSet::new(); a_graph.sequences.len()];
+    let (a_colors, b_colors) = refine_colors(&a_graph, &b_graph, &no_paths, &no_paths);
+    match find_mapping(&a_graph, &b_graph, &a_colors, &b_colors, |_| true) {
+        Some(mapping) => Err(EquivalenceError::PathRoute(
+            wrong_route(&mapping).unwrap_or_default(),
+        )),
+        None => Err(EquivalenceError::Structure),
+    }
+}
+
+/// Returns true if the two graphs are equivalent, see `check_equivalence`
+pub fn equivalent<A, B>(a: &A, b: &B) -> bool
+where
+    A: PathHandleGraph,
+    for<'a> &'a A: HandleGraphRef,
+    B: PathHandleGraph,
+    for<'a> &'a B: HandleGraphRef,
+{
+    check_equivalence(a, b).is_ok()
+}
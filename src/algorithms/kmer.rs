@@ -0,0 +1,204 @@
+use fnv::FnvHashMap;
+
+use crate::{
+    handle::{Direction, Handle},
+    handlegraph::{HandleGraphRef, HandleSequences},
+    pathgraph::PathHandleGraph,
+};
+
+/// Index from each k-mer to the positions where it starts, as the
+/// oriented handle and the offset along it. K-mers are read on both
+/// strands, so the reverse complement of a k-mer starting on a forward
+/// handle is found on the reverse handles it ends on.
+#[derive(Debug, Default, Clone)]
+pub struct KmerIndex {
+    pub k: usize,
+    pub kmers: FnvHashMap<Vec<u8>, Vec<(Handle, usize)>>,
+    /// Number of starting positions left out because walking the graph
+    /// from them crossed more walks than the given limit
+    pub skipped: usize,
+}
+
+impl KmerIndex {
+    fn new(k: usize) -> Self {
+        KmerIndex {
+            k,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the positions where the k-mer starts, sorted
+    pub fn positions(&self, kmer: &[u8]) -> &[(Handle, usize)] {
+        self.kmers.get(kmer).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Number of distinct k-mers in the index
+    pub fn len(&self) -> usize {
+        self.kmers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kmers.is_empty()
+    }
+
+    fn insert(&mut self, kmer: Vec<u8>, handle: Handle, offset: usize) {
+        self.kmers.entry(kmer).or_default().push((handle, offset));
+    }
+
+    fn finish(&mut self) {
+        for positions in self.kmers.values_mut() {
+            positions.sort();
+            positions.dedup();
+        }
+    }
+}
+
+/// Collects the sequences of `len` bases spelled by the walks starting
+/// right after the handle. Walks reaching a dead end first are kept with
+/// the bases they spell. Returns None if more than `max_walks` walks are
+/// found.
+fn extensions<G: HandleGraphRef>(
+    graph: G,
+    handle: Handle,
+    len: usize,
+    max_walks: usize,
+) -> Option<Vec<Vec<u8>>> {
+    let mut found = vec![];
+    let mut stack: Vec<(Handle, Vec<u8>)> = graph
+        .neighbors(handle, Direction::Right)
+        .map(|next| (next, vec![]))
+        .collect();
+
+    while let Some((next, mut seq)) = stack.pop() {
+        seq.extend(graph.sequence_iter(next).take(len - seq.len()));
+        let mut after = graph.neighbors(next, Direction::Right).peekable();
+        if seq.len() == len || after.peek().is_none() {
+            found.push(seq);
+            if found.len() > max_walks {
+                return None;
+            }
+        } else {
+            for after in after {
+                stack.push((after, seq.clone()));
+            }
+            if stack.len() > max_walks {
+                return None;
+            }
+        }
+    }
+
+    Some(found)
+}
+
+/// Function that builds the index of the k-mers spelled by any walk in
+/// the graph, on both strands, including the k-mers crossing node
+/// boundaries.\
+/// In dense regions the number of walks grows exponentially with `k`, so
+/// the k-mers crossing the end of a handle are left out if there are
+/// more than `max_walks` walks of `k - 1` bases leaving it; they are
+/// counted in `skipped`.
+/// # Example
+/// ```ignore
+/// let index = kmer_index(&graph, 11, 256);
+/// for (handle, offset) in index.positions(b"GATTACAGATT") {
+///     println!("{}{} {}", handle.id(), if handle.is_reverse() { "-" } else { "+" }, offset);
+/// }
+/// ```
+pub fn kmer_index<G: HandleGraphRef>(graph: G, k: usize, max_walks: usize) -> KmerIndex {
+    let mut index = KmerIndex::new(k);
+    if k == 0 {
+        return index;
+    }
+
+    let handles = graph
+        .all_handles()
+        .flat_map(|h| vec![h, h.flip()].into_iter());
+
+    for handle in handles {
+        let seq = graph.sequence(handle);
+        let len = seq.len();
+
+        // the k-mers inside the handle
+        for offset in 0..(len + 1).saturating_sub(k) {
+            index.insert(seq[offset..offset + k].to_vec(), handle, offset);
+        }
+
+        // the k-mers crossing the end of the handle: all of them are
+        // prefixes of the walks of up to k - 1 bases after the handle
+        let first_crossing = (len + 1).saturating_sub(k);
+        let walks = match extensions(graph, handle, k - 1, max_walks) {
+            Some(walks) => walks,
+            None => {
+                index.skipped += len - first_crossing;
+                continue;
+            }
+        };
+
+        for offset in first_crossing..len {
+            let needed = k - (len - offset);
+            let mut kmers: Vec<Vec<u8>> = walks
+                .iter()
+                .filter(|walk| walk.len() >= needed)
+                .map(|walk| {
+                    let mut kmer = seq[offset..].to_vec();
+                    kmer.extend_from_slice(&walk[..needed]);
+                    kmer
+                })
+                .collect();
+            kmers.sort();
+            kmers.dedup();
+            for kmer in kmers {
+                index.insert(kmer, handle, offset);
+            }
+        }
+    }
+
+    index.finish();
+    index
+}
+
+/// Function that builds the index of the k-mers spelled by the embedded
+/// paths, on both strands. Circular paths are read as linear.
+/// # Example
+/// ```ignore
+/// let index = path_kmer_index(&graph, 31);
+/// println!("{} distinct k-mers on the paths", index.len());
+/// ```
+pub fn path_kmer_index<G>(graph: &G, k: usize) -> KmerIndex
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let mut index = KmerIndex::new(k);
+    if k == 0 {
+        return index;
+    }
+
+    for path in graph.paths_iter() {
+        let forward: Vec<Handle> = graph
+            .steps_iter(path)
+            .filter_map(|step| graph.handle_of_step(&step))
+            .collect();
+        let reverse: Vec<Handle> = forward.iter().rev().map(|h| h.flip()).collect();
+
+        for handles in [forward, reverse].iter() {
+            // the sequence of the walk, and the handle and offset of
+            // every base in it
+            let mut seq = vec![];
+            let mut bases = vec![];
+            for &handle in handles.iter() {
+                let node_seq = graph.sequence(handle);
+                bases.extend((0..node_seq.len()).map(|offset| (handle, offset)));
+                seq.extend(node_seq);
+            }
+
+            for start in 0..(seq.len() + 1).saturating_sub(k) {
+                let (handle, offset) = bases[start];
+                index.insert(seq[start..start + k].to_vec(), handle, offset);
+            }
+        }
+    }
+
+    index.finish();
+    index
+}
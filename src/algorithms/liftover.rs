@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use crate::{
+    handle::Handle,
+    handlegraph::{HandleGraphRef, HandleSequences},
+    pathgraph::PathHandleGraph,
+};
+
+/// A position lifted to another path; `is_reverse` is true when the
+/// two paths cross the node in opposite orientations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiftedPosition {
+    pub position: usize,
+    pub is_reverse: bool,
+}
+
+/// A piece of an interval lifted to another path. Every base of the
+/// source interval ends up in exactly one `Mapped` or `Unmapped` piece.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiftedInterval {
+    /// The bases in `from` map to the bases in `to`, on the opposite
+    /// strand if `is_reverse` is true
+    Mapped {
+        from: Range<usize>,
+        to: Range<usize>,
+        is_reverse: bool,
+    },
+    /// The bases in `from` lie on nodes the target path doesn't cross
+    Unmapped { from: Range<usize> },
+}
+
+/// Returns the step of the path containing the base at the position,
+/// along with its handle and the position where the step starts
+pub(crate) fn step_containing<G>(
+    graph: &G,
+    path: &G::PathHandle,
+    pos: usize,
+) -> Option<(G::StepHandle, Handle, usize)>
+where
+    G: PathHandleGraph,
+{
+    if pos >= graph.path_bases_len(path)? {
+        return None;
+    }
+    // the base at position 0 belongs to the first step
+    let step = if pos == 0 {
+        graph.path_begin(path)
+    } else {
+        graph.step_at_position(path, pos)?
+    };
+    let handle = graph.handle_of_step(&step)?;
+    let start = graph.position_of_step(&step)?;
+    Some((step, handle, start))
+}
+
+/// Returns the pieces of the range lying on each step of the path, with
+/// the handle of the step and the position where the step starts. The
+/// part of the range past the end of the path is left out.
+pub(crate) fn steps_over<G>(
+    graph: &G,
+    path: &G::PathHandle,
+    range: Range<usize>,
+) -> Vec<(Handle, usize, Range<usize>)>
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let mut pieces = vec![];
+
+    let mut pos = range.start;
+    if let Some((mut step, mut handle, mut start)) = step_containing(graph, path, pos) {
+        while pos < range.end {
+            let len = graph.node_len(handle);
+            let end = std::cmp::min(start + len, range.end);
+            pieces.push((handle, start, pos..end));
+
+            pos = end;
+            start += len;
+            step = graph.next_step(&step);
+            handle = match graph.handle_of_step(&step) {
+                Some(h) => h,
+                None => break,
+            };
+        }
+    }
+
+    pieces
+}
+
+/// Returns the earliest step of the target path on the node among the
+/// ones `occurrences_iter` reports, along with its handle and the
+/// position where the step starts
+fn step_on_path<G>(graph: &G, handle: Handle, to: &G::PathHandle) -> Option<(Handle, usize)>
+where
+    G: PathHandleGraph,
+    G::PathHandle: PartialEq,
+{
+    graph
+        .occurrences_iter(handle)
+        .filter(|step| graph.path_handle_of_step(step) == *to)
+        .filter_map(|step| Some((graph.handle_of_step(&step)?, graph.position_of_step(&step)?)))
+        .min_by_key(|(_, start)| *start)
+}
+
+/// Function that lifts a position on a path to the homologous position on
+/// another path, through the node the two paths share. Returns None if
+/// the position is outside the path, or if the target path doesn't
+/// cross the node at that position.\
+/// If the target path crosses the node more than once, the earliest
+/// crossing reported by `occurrences_iter` is used. A `HashGraph` only
+/// reports one crossing for each path on a node, usually the last one
+/// added, so the other crossings are never used.
+/// # Example
+/// ```ignore
+/// let grch38 = graph.name_to_path_handle(b"GRCh38#chr1").unwrap();
+/// let chm13 = graph.name_to_path_handle(b"CHM13#chr1").unwrap();
+///
+/// if let Some(lifted) = lift_position(&graph, &grch38, 1_234_567, &chm13) {
+///     println!("{} ({})", lifted.position, if lifted.is_reverse { "-" } else { "+" });
+/// }
+/// ```
+pub fn lift_position<G>(
+    graph: &G,
+    from: &G::PathHandle,
+    pos: usize,
+    to: &G::PathHandle,
+) -> Option<LiftedPosition>
+where
+    G: PathHandleGraph,
+    G::PathHandle: PartialEq,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let (_, handle, start) = step_containing(graph, from, pos)?;
+    let (target, target_start) = step_on_path(graph, handle, to)?;
+
+    let offset = pos - start;
+    let is_reverse = handle.is_reverse() != target.is_reverse();
+    let position = if is_reverse {
+        target_start + graph.node_len(handle) - 1 - offset
+    } else {
+        target_start + offset
+    };
+
+    Some(LiftedPosition {
+        position,
+        is_reverse,
+    })
+}
+
+/// Function that lifts an interval on a path to another path, splitting
+/// it into the pieces that are contiguous on the target path and the
+/// pieces that can't be mapped. The bases of the interval past the end
+/// of the path are reported as unmapped. Nodes the target path crosses
+/// more than once are lifted like in `lift_position`.
+/// # Example
+/// ```ignore
+/// // Nodes: 1: AC, 2: GT, 3: TTT, 4: CA
+/// // Path A: 1+ 2+ 4+
+/// // Path B: 1+ 3+ 4+
+///
+/// // [Mapped { from: 1..2, to: 1..2, .. }, Unmapped { from: 2..4 }, Mapped { from: 4..6, to: 5..7, .. }]
+/// let lifted = lift_interval(&graph, &path_a, 1..6, &path_b);
+/// ```
+pub fn lift_interval<G>(
+    graph: &G,
+    from: &G::PathHandle,
+    range: Range<usize>,
+    to: &G::PathHandle,
+) -> Vec<LiftedInterval>
+where
+    G: PathHandleGraph,
+    G::PathHandle: PartialEq,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let mut lifted: Vec<LiftedInterval> = vec![];
+
+    let mut push = |piece: LiftedInterval| {
+        use LiftedInterval::*;
+        // extend the last piece if the new one continues it
+        match (lifted.last_mut(), &piece) {
+            (Some(Unmapped { from: last }), Unmapped { from }) => {
+                last.end = from.end;
+            }
+            (
+                Some(Mapped {
+                    from: last_from,
+                    to: last_to,
+                    is_reverse: last_rev,
+                }),
+                Mapped {
+                    from,
+                    to,
+                    is_reverse,
+                },
+            ) if last_rev == is_reverse
+                && ((!is_reverse && last_to.end == to.start)
+                    || (*is_reverse && to.end == last_to.start)) =>
+            {
+                last_from.end = from.end;
+                if *is_reverse {
+                    last_to.start = to.start;
+                } else {
+                    last_to.end = to.end;
+                }
+            }
+            _ => lifted.push(piece),
+        }
+    };
+
+    let mut pos = range.start;
+    for (handle, start, piece) in steps_over(graph, from, range.clone()) {
+        let len = graph.node_len(handle);
+        match step_on_path(graph, handle, to) {
+            Some((target, target_start)) => {
+                let is_reverse = handle.is_reverse() != target.is_reverse();
+                let (a, b) = (piece.start - start, piece.end - start);
+                let to = if is_reverse {
+                    target_start + len - b..target_start + len - a
+                } else {
+                    target_start + a..target_start + b
+                };
+                push(LiftedInterval::Mapped {
+                    from: piece.clone(),
+                    to,
+                    is_reverse,
+                });
+            }
+            None => push(LiftedInterval::Unmapped {
+                from: piece.clone(),
+            }),
+        }
+        pos = piece.end;
+    }
+
+    if pos < range.end {
+        push(LiftedInterval::Unmapped {
+            from: pos..range.end,
+        });
+    }
+
+    lifted
+}
@@ -0,0 +1,97 @@
+use fnv::FnvHashMap;
+
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::{AllEdges, AllHandles, HandleGraph, HandleGraphRef, HandleSequences},
+    mutablehandlegraph::AdditiveHandleGraph,
+    pathgraph::PathHandleGraph,
+};
+
+/// How the ids of the merged graph are made not to collide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeIds {
+    /// Every node gets a new id, shifted past the largest id of the target
+    Offset,
+    /// Nodes with the same id and sequence as a node of the target are
+    /// unified with it, the others are shifted as in `Offset`
+    Squeeze,
+}
+
+/// Returns the name, or the name with the smallest `.n` suffix, that
+/// isn't already a path of the graph
+fn unique_path_name<G: PathHandleGraph>(graph: &G, name: &[u8]) -> Vec<u8> {
+    let mut unique = name.to_vec();
+    let mut n = 0;
+    while graph.has_path(&unique) {
+        n += 1;
+        unique = [name, format!(".{}", n).as_bytes()].concat();
+    }
+    unique
+}
+
+/// Function that copies the nodes, edges and paths of a graph into
+/// another one, and returns the map from the ids of the source graph to
+/// the ids they have in the target.\
+/// Paths whose name is already taken in the target get the first free
+/// `.1`, `.2`, ... suffix. Edges and path steps on nodes missing from the
+/// source are not copied.
+/// # Example
+/// ```ignore
+/// let mut genome = HashGraph::new();
+/// for chromosome in chromosomes.iter() {
+///     merge_graphs(&mut genome, chromosome, MergeIds::Offset);
+/// }
+/// ```
+pub fn merge_graphs<G, S>(target: &mut G, source: &S, ids: MergeIds) -> FnvHashMap<NodeId, NodeId>
+where
+    G: HandleGraph + AdditiveHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+    S: PathHandleGraph,
+    for<'a> &'a S: HandleGraphRef,
+{
+    let mut handles: Vec<Handle> = source.all_handles().collect();
+    handles.sort();
+
+    // the smallest id of the source goes right after the largest of the
+    // target
+    let first_id = u64::from(target.max_node_id()) + 1;
+    let source_min = handles.first().map(|h| u64::from(h.id())).unwrap_or(0);
+
+    let mut map: FnvHashMap<NodeId, NodeId> = FnvHashMap::default();
+    for handle in handles {
+        let sequence = source.sequence(handle);
+        let id = handle.id();
+        let squeezed = ids == MergeIds::Squeeze
+            && (&*target).has_node(id)
+            && (&*target).sequence(Handle::pack(id, false)) == sequence;
+        if squeezed {
+            map.insert(id, id);
+        } else {
+            let new_id = NodeId::from(u64::from(id) - source_min + first_id);
+            target.create_handle(&sequence, new_id);
+            map.insert(id, new_id);
+        }
+    }
+
+    // edges and steps on nodes the source doesn't have are left out
+    let remap = |h: Handle| map.get(&h.id()).map(|&id| Handle::pack(id, h.is_reverse()));
+    for Edge(left, right) in source.all_edges() {
+        if let (Some(left), Some(right)) = (remap(left), remap(right)) {
+            target.create_edge(Edge(left, right));
+        }
+    }
+
+    let mut paths: Vec<&S::PathHandle> = source.paths_iter().collect();
+    paths.sort_by_key(|p| source.path_handle_to_name(p));
+    for path in paths {
+        let name = unique_path_name(target, source.path_handle_to_name(path));
+        let new_path = target.create_path_handle(&name, source.is_circular(path));
+        for step in source.steps_iter(path) {
+            if let Some(handle) = source.handle_of_step(&step).and_then(remap) {
+                target.append_step(&new_path, handle);
+            }
+        }
+    }
+
+    map
+}
@@ -0,0 +1,116 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::{AllHandles, HandleNeighbors, HandleSequences},
+    hashgraph::HashGraph,
+    mutablehandlegraph::MutableHandleGraph,
+};
+
+/// How many nodes `normalize` merged into a sibling, and how many times
+/// it divided a node to expose a prefix or suffix shared with a sibling
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeReport {
+    pub merged: usize,
+    pub divided: usize,
+}
+
+/// The children of the handle grouped by their predecessors: only
+/// siblings with the same predecessors can be merged without adding
+/// new walks to the graph
+fn sibling_groups(graph: &HashGraph, handle: Handle) -> Vec<Vec<Handle>> {
+    let mut groups: FnvHashMap<Vec<Handle>, Vec<Handle>> = FnvHashMap::default();
+    for child in graph.neighbors(handle, Direction::Right) {
+        let mut parents: Vec<Handle> = graph.neighbors(child, Direction::Left).collect();
+        parents.sort();
+        parents.dedup();
+        let group = groups.entry(parents).or_default();
+        if group.iter().all(|h| h.id() != child.id()) {
+            group.push(child);
+        }
+    }
+    let mut groups: Vec<Vec<Handle>> = groups.into_values().collect();
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups.sort();
+    groups
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// One round of merging and dividing, touching every node at most once
+fn normalize_round(graph: &mut HashGraph, report: &mut NormalizeReport) -> bool {
+    let mut handles: Vec<Handle> = graph.all_handles().collect();
+    handles.sort();
+
+    let mut touched: FnvHashSet<NodeId> = FnvHashSet::default();
+    for handle in handles {
+        for &h in [handle, handle.flip()].iter() {
+            if !graph.graph.contains_key(&h.id()) {
+                continue;
+            }
+            // the groups are stale once a sibling has changed
+            'groups: for group in sibling_groups(graph, h) {
+                if group.iter().any(|s| touched.contains(&s.id())) {
+                    continue;
+                }
+                let mut by_base: FnvHashMap<u8, Vec<(Handle, Vec<u8>)>> = FnvHashMap::default();
+                for &s in group.iter() {
+                    let seq = graph.sequence(s);
+                    by_base.entry(seq[0]).or_default().push((s, seq));
+                }
+                let mut by_base: Vec<_> = by_base.into_iter().collect();
+                by_base.sort();
+
+                if let Some((_, siblings)) = by_base.into_iter().find(|(_, s)| s.len() > 1) {
+                    let prefix = siblings
+                        .iter()
+                        .skip(1)
+                        .map(|(_, seq)| common_prefix(&siblings[0].1, seq))
+                        .min()
+                        .unwrap();
+                    touched.extend(siblings.iter().map(|(s, _)| s.id()));
+
+                    if siblings.iter().all(|(_, seq)| seq.len() == prefix) {
+                        let handles: Vec<Handle> = siblings.iter().map(|(s, _)| *s).collect();
+                        graph.merge_handles(&handles);
+                        report.merged += handles.len() - 1;
+                    } else {
+                        // expose the shared prefix, the pieces are merged
+                        // in the next round
+                        for &(s, ref seq) in siblings.iter() {
+                            if seq.len() > prefix {
+                                graph.divide_handle(s, vec![prefix]);
+                                report.divided += 1;
+                            }
+                        }
+                    }
+                    break 'groups;
+                }
+            }
+        }
+    }
+    !touched.is_empty()
+}
+
+/// Function that merges the sibling nodes of the graph, the ones reached
+/// from the same predecessors, that spell the same sequence, dividing
+/// siblings that only share a prefix (or, going left, a suffix) first.
+/// Paths are rewritten through the merged nodes, and the pass is
+/// repeated until nothing changes. The sequences spelled by the walks of
+/// the graph and by its paths are not changed.
+/// # Example
+/// ```ignore
+/// // 1: A -> 2: CGT, 1: A -> 3: CGA
+/// // becomes 1: A -> 2: CG -> 2': T, 2: CG -> 3': A
+/// let report = normalize(&mut graph);
+/// println!("{} merged, {} divided", report.merged, report.divided);
+/// ```
+pub fn normalize(graph: &mut HashGraph) -> NormalizeReport {
+    let mut report = NormalizeReport::default();
+    while normalize_round(graph, &mut report) {}
+    report
+}
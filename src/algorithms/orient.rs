@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::{AllHandles, HandleGraphRef, HandleNeighbors},
+    mutablehandlegraph::MutableHandleGraph,
+    pathgraph::PathHandleGraph,
+};
+
+/// The nodes flipped by `orient_to_path`, and the nodes the reference
+/// path crosses in both orientations, which are left in the orientation
+/// of their first step
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrientReport {
+    pub flipped: Vec<NodeId>,
+    pub conflicts: Vec<NodeId>,
+}
+
+/// Function that flips the nodes of the graph so that the reference path
+/// only traverses forward handles. The nodes not on the path are then
+/// oriented, starting from the oriented ones, so that as many edges as
+/// possible go from a forward handle to a forward handle.\
+/// Returns None if the graph has no path with the given name.
+/// # Example
+/// ```ignore
+/// // path GRCh38#chr1: 1+ 2- 4+ 5-
+/// let report = orient_to_path(&mut graph, b"GRCh38#chr1").unwrap();
+/// // [2, 5]
+/// println!("{:?}", report.flipped);
+/// ```
+pub fn orient_to_path<G>(graph: &mut G, reference: &[u8]) -> Option<OrientReport>
+where
+    G: MutableHandleGraph + PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let path = graph.name_to_path_handle(reference)?;
+    let steps: Vec<Handle> = graph
+        .steps_iter(&path)
+        .filter_map(|step| graph.handle_of_step(&step))
+        .collect();
+
+    let mut report = OrientReport::default();
+    let mut oriented: FnvHashSet<NodeId> = FnvHashSet::default();
+    let mut queue: VecDeque<NodeId> = VecDeque::new();
+
+    // the orientation of the first step on each node
+    let mut first: FnvHashMap<NodeId, bool> = FnvHashMap::default();
+    for handle in steps {
+        match first.get(&handle.id()) {
+            Some(&is_reverse) => {
+                if is_reverse != handle.is_reverse() && !report.conflicts.contains(&handle.id()) {
+                    report.conflicts.push(handle.id());
+                }
+            }
+            None => {
+                first.insert(handle.id(), handle.is_reverse());
+                oriented.insert(handle.id());
+                if handle.is_reverse() {
+                    graph.apply_orientation(handle);
+                    report.flipped.push(handle.id());
+                }
+                queue.push_back(handle.id());
+            }
+        }
+    }
+
+    let mut handles: Vec<Handle> = {
+        let graph_ref: &G = graph;
+        graph_ref.all_handles().collect()
+    };
+    handles.sort();
+    let mut seeds = handles.into_iter();
+
+    loop {
+        while let Some(id) = queue.pop_front() {
+            let fwd = Handle::pack(id, false);
+            let graph_ref: &G = graph;
+            // a neighbour on the right should be forward, and so should
+            // one on the left
+            let mut reached: Vec<Handle> = graph_ref.neighbors(fwd, Direction::Right).collect();
+            reached.extend(graph_ref.neighbors(fwd, Direction::Left));
+
+            for next in reached {
+                if oriented.insert(next.id()) {
+                    if next.is_reverse() {
+                        graph.apply_orientation(next);
+                        report.flipped.push(next.id());
+                    }
+                    queue.push_back(next.id());
+                }
+            }
+        }
+        // the components the path doesn't reach keep the orientation of
+        // their smallest node
+        match seeds.find(|h| !oriented.contains(&h.id())) {
+            Some(seed) => {
+                oriented.insert(seed.id());
+                queue.push_back(seed.id());
+            }
+            None => break,
+        }
+    }
+
+    report.flipped.sort();
+    report.conflicts.sort();
+    Some(report)
+}
@@ -0,0 +1,153 @@
+use bstr::BString;
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Direction, Edge, Handle},
+    handlegraph::{AllHandles, HandleNeighbors, HandleSequences},
+    hashgraph::HashGraph,
+    mutablehandlegraph::SubtractiveHandleGraph,
+};
+
+/// The edges removed by `prune_complex_regions`, and the ones that were
+/// marked but put back because a path crosses them, in the orientation
+/// given by `Edge::edge_handle`, along with the paths that were removed
+/// because they crossed a removed edge
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub removed: Vec<Edge>,
+    pub restored: Vec<Edge>,
+    pub paths: Vec<BString>,
+}
+
+/// A default bound for `prune_complex_regions`: the number of walks
+/// extended from a single starting handle before the search gives up
+pub const MAX_WALKS: usize = 1 << 16;
+
+/// Marks the edges where a walk of `remaining` more bases, starting
+/// after the handle, crosses more than `edge_max` edges. At most
+/// `max_walks` walks are extended, after that the edges where the
+/// others would have continued are marked too
+fn mark_walks(
+    graph: &HashGraph,
+    start: Handle,
+    remaining: usize,
+    edge_max: usize,
+    max_walks: usize,
+    marked: &mut FnvHashSet<Edge>,
+) {
+    let mut walks = 0;
+    let mut stack = vec![(start, remaining, 0)];
+    while let Some((handle, remaining, crossed)) = stack.pop() {
+        for next in graph.neighbors(handle, Direction::Right) {
+            if crossed + 1 > edge_max {
+                marked.insert(Edge::edge_handle(handle, next));
+                continue;
+            }
+            let len = graph.node_len(next);
+            if remaining > len {
+                if walks == max_walks {
+                    marked.insert(Edge::edge_handle(handle, next));
+                } else {
+                    walks += 1;
+                    stack.push((next, remaining - len, crossed + 1));
+                }
+            }
+        }
+    }
+}
+
+/// Function that returns a copy of the graph without the edges of its
+/// most complex regions, to keep the number of k-mers of the graph in
+/// check before indexing it, in the manner of `vg prune`: every edge
+/// where a walk of `k` bases crosses more than `edge_max` edges is
+/// removed.\
+/// Dense regions can have too many walks to enumerate, so at most
+/// `max_walks` are followed from each handle, and the edges where the
+/// search stopped are removed as well, as if they were too complex.\
+/// If `restore_paths` is true the edges crossed by a path are kept,
+/// otherwise the paths crossing a removed edge are removed too. The
+/// nodes are always kept.
+/// # Example
+/// ```ignore
+/// let (pruned, report) = prune_complex_regions(&graph, 24, 3, MAX_WALKS, true);
+/// println!("removed {} edges", report.removed.len());
+/// let index = kmer_index(&pruned, 24, 100_000);
+/// ```
+pub fn prune_complex_regions(
+    graph: &HashGraph,
+    k: usize,
+    edge_max: usize,
+    max_walks: usize,
+    restore_paths: bool,
+) -> (HashGraph, PruneReport) {
+    let mut marked: FnvHashSet<Edge> = FnvHashSet::default();
+    if k > 1 {
+        for handle in graph.all_handles() {
+            for &h in [handle, handle.flip()].iter() {
+                // walks starting on the last base of the handle cross
+                // the most edges
+                mark_walks(graph, h, k - 1, edge_max, max_walks, &mut marked);
+            }
+        }
+    }
+
+    let mut path_edges: FnvHashSet<Edge> = FnvHashSet::default();
+    let mut crossing_paths: Vec<BString> = vec![];
+    for path in graph.paths.values() {
+        let mut edges: Vec<Edge> = path
+            .nodes
+            .windows(2)
+            .map(|w| Edge::edge_handle(w[0], w[1]))
+            .collect();
+        if path.is_circular && path.nodes.len() > 1 {
+            edges.push(Edge::edge_handle(
+                *path.nodes.last().unwrap(),
+                path.nodes[0],
+            ));
+        }
+        if edges.iter().any(|e| marked.contains(e)) {
+            crossing_paths.push(path.name.clone());
+        }
+        path_edges.extend(edges);
+    }
+
+    let mut report = PruneReport::default();
+    for edge in marked {
+        if restore_paths && path_edges.contains(&edge) {
+            report.restored.push(edge);
+        } else {
+            report.removed.push(edge);
+        }
+    }
+    report.removed.sort();
+    report.restored.sort();
+    if !restore_paths {
+        crossing_paths.sort();
+        report.paths = crossing_paths;
+    }
+
+    let mut pruned = graph.clone();
+    for name in report.paths.iter() {
+        pruned.remove_path(name);
+    }
+    for &Edge(left, right) in report.removed.iter() {
+        if let Some(node) = pruned.graph.get_mut(&left.id()) {
+            let edges = if left.is_reverse() {
+                &mut node.left_edges
+            } else {
+                &mut node.right_edges
+            };
+            edges.retain(|&h| h != right);
+        }
+        if let Some(node) = pruned.graph.get_mut(&right.id()) {
+            let edges = if right.is_reverse() {
+                &mut node.right_edges
+            } else {
+                &mut node.left_edges
+            };
+            edges.retain(|&h| h != left.flip());
+        }
+    }
+
+    (pruned, report)
+}
@@ -0,0 +1,98 @@
+use crate::{
+    handle::{Direction, Handle},
+    handlegraph::HandleGraphRef,
+};
+
+/// A walk spelling the query: it starts at offset `start` of the first
+/// handle and ends right before offset `end` of the last one, with both
+/// offsets along the handles in their own orientation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SequenceMatch {
+    pub walk: Vec<Handle>,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn bases_match(seq: &[u8], query: &[u8]) -> bool {
+    seq.len() == query.len()
+        && seq
+            .iter()
+            .zip(query.iter())
+            .all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+/// Function that finds every oriented position where the query occurs
+/// as a walk in the graph, including walks spanning several nodes.
+/// Both strands are searched, so the matches on the reverse strand are
+/// walks on reverse handles. The comparison ignores the case of the
+/// bases, and the matches are sorted.
+/// # Example
+/// ```ignore
+/// // Nodes: 1: ACGT, 2: GG, 3: TTTTT, 4: CA
+/// // Edges: 1+ -> 2+, 1+ -> 3+, 2+ -> 4+, 3+ -> 4+
+///
+/// // [SequenceMatch { walk: [1+, 2+, 4+], start: 2, end: 1 }]
+/// let matches = find_sequence(&graph, b"GTGGC");
+/// ```
+pub fn find_sequence<G: HandleGraphRef>(graph: G, query: &[u8]) -> Vec<SequenceMatch> {
+    let mut matches = vec![];
+    if query.is_empty() {
+        return matches;
+    }
+
+    let handles = graph
+        .all_handles()
+        .flat_map(|h| vec![h, h.flip()].into_iter());
+
+    for handle in handles {
+        let seq = graph.sequence(handle);
+
+        for start in 0..seq.len() {
+            if !seq[start].eq_ignore_ascii_case(&query[0]) {
+                continue;
+            }
+
+            let in_node = std::cmp::min(seq.len() - start, query.len());
+            if !bases_match(&seq[start..start + in_node], &query[..in_node]) {
+                continue;
+            }
+            if in_node == query.len() {
+                matches.push(SequenceMatch {
+                    walk: vec![handle],
+                    start,
+                    end: start + in_node,
+                });
+                continue;
+            }
+
+            // extend the match through the neighbors, keeping the walk
+            // so far and the number of bases of the query it matches
+            let mut stack = vec![(vec![handle], in_node)];
+            while let Some((walk, matched)) = stack.pop() {
+                let last = *walk.last().unwrap();
+                for next in graph.neighbors(last, Direction::Right) {
+                    let next_seq = graph.sequence(next);
+                    let len = std::cmp::min(next_seq.len(), query.len() - matched);
+                    if !bases_match(&next_seq[..len], &query[matched..matched + len]) {
+                        continue;
+                    }
+
+                    let mut next_walk = walk.clone();
+                    next_walk.push(next);
+                    if matched + len == query.len() {
+                        matches.push(SequenceMatch {
+                            walk: next_walk,
+                            start,
+                            end: len,
+                        });
+                    } else {
+                        stack.push((next_walk, matched + len));
+                    }
+                }
+            }
+        }
+    }
+
+    matches.sort();
+    matches
+}
@@ -0,0 +1,109 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use fnv::FnvHashMap;
+
+use crate::{
+    handle::{Direction, Handle},
+    handlegraph::HandleGraphRef,
+};
+
+/// The result of a shortest path search: the walk of oriented handles
+/// from the start position to the end position (both included), and
+/// the distance in bases between the two positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortestPath {
+    pub walk: Vec<Handle>,
+    pub distance: usize,
+}
+
+/// Function that computes the minimum distance in bases between two
+/// oriented positions, each given as a handle and an offset along the
+/// handle in its own orientation.\
+/// The search only follows the graph in the direction of the handles, so
+/// the walk returned is a valid walk in the bidirected graph, and it
+/// stops as soon as every candidate is further than `max_dist` bases.
+/// # Example
+/// ```ignore
+/// // Nodes: 1: ACGT, 2: GG, 3: TTTTT, 4: CA
+/// // Edges: 1+ -> 2+, 1+ -> 3+, 2+ -> 4+, 3+ -> 4+
+///
+/// let path = shortest_path(&graph, (h1, 1), (h4, 1), 100).unwrap();
+///
+/// // 3 bases left on 1+, 2 bases on 2+, 1 base on 4+
+/// assert_eq!(path.distance, 6);
+/// assert_eq!(path.walk, vec![h1, h2, h4]);
+/// ```
+pub fn shortest_path<G: HandleGraphRef>(
+    graph: G,
+    from: (Handle, usize),
+    to: (Handle, usize),
+    max_dist: usize,
+) -> Option<ShortestPath> {
+    let (from_handle, from_offset) = from;
+    let (to_handle, to_offset) = to;
+
+    // the target is further along on the same handle
+    if from_handle == to_handle && from_offset <= to_offset {
+        let distance = to_offset - from_offset;
+        return if distance <= max_dist {
+            Some(ShortestPath {
+                walk: vec![from_handle],
+                distance,
+            })
+        } else {
+            None
+        };
+    }
+
+    // distance from the starting position to the start of each
+    // handle, and the handle each one was reached from; `None` marks
+    // the handles reached directly from the starting position
+    let mut distances: FnvHashMap<Handle, usize> = FnvHashMap::default();
+    let mut previous: FnvHashMap<Handle, Option<Handle>> = FnvHashMap::default();
+    let mut queue: BinaryHeap<Reverse<(usize, Handle)>> = BinaryHeap::new();
+
+    let first_dist = graph.node_len(from_handle).saturating_sub(from_offset);
+    for next in graph.neighbors(from_handle, Direction::Right) {
+        if first_dist < *distances.get(&next).unwrap_or(&usize::MAX) {
+            distances.insert(next, first_dist);
+            previous.insert(next, None);
+            queue.push(Reverse((first_dist, next)));
+        }
+    }
+
+    while let Some(Reverse((dist, handle))) = queue.pop() {
+        if dist > distances[&handle] {
+            // stale entry, the handle was already reached by a shorter walk
+            continue;
+        }
+        if dist + to_offset > max_dist {
+            return None;
+        }
+        if handle == to_handle {
+            let mut walk = vec![handle];
+            let mut current = handle;
+            while let Some(prev) = previous[&current] {
+                walk.push(prev);
+                current = prev;
+            }
+            walk.push(from_handle);
+            walk.reverse();
+            return Some(ShortestPath {
+                walk,
+                distance: dist + to_offset,
+            });
+        }
+
+        let next_dist = dist + graph.node_len(handle);
+        for next in graph.neighbors(handle, Direction::Right) {
+            if next_dist < *distances.get(&next).unwrap_or(&usize::MAX) {
+                distances.insert(next, next_dist);
+                previous.insert(next, Some(handle));
+                queue.push(Reverse((next_dist, next)));
+            }
+        }
+    }
+
+    None
+}
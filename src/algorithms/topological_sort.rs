@@ -0,0 +1,61 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use fnv::FnvHashMap;
+
+use crate::{
+    handle::{Direction, Handle},
+    handlegraph::HandleGraphRef,
+};
+
+/// Function that sorts the forward handles of a graph so that every
+/// edge goes from an earlier handle to a later one, using Kahn's
+/// algorithm and picking the smallest handle first when there is a
+/// choice. Edges reaching the reverse strand of a node are ignored, so
+/// graphs with inversions should be oriented first.\
+/// Returns None if the graph has a cycle.
+/// # Example
+/// ```ignore
+/// // Nodes: 1, 2, 3, 4
+/// // Edges: 1 -> 3, 3 -> 2, 2 -> 4
+///
+/// // Some([1+, 3+, 2+, 4+])
+/// let order = topological_sort(&graph);
+/// ```
+pub fn topological_sort<G: HandleGraphRef>(graph: G) -> Option<Vec<Handle>> {
+    let mut in_degree: FnvHashMap<Handle, usize> = FnvHashMap::default();
+    for handle in graph.all_handles() {
+        let degree = graph
+            .neighbors(handle, Direction::Left)
+            .filter(|prev| !prev.is_reverse())
+            .count();
+        in_degree.insert(handle, degree);
+    }
+
+    let mut queue: BinaryHeap<Reverse<Handle>> = in_degree
+        .iter()
+        .filter(|(_, &degree)| degree == 0)
+        .map(|(&handle, _)| Reverse(handle))
+        .collect();
+
+    let mut order = Vec::with_capacity(in_degree.len());
+    while let Some(Reverse(handle)) = queue.pop() {
+        order.push(handle);
+        for next in graph.neighbors(handle, Direction::Right) {
+            if next.is_reverse() {
+                continue;
+            }
+            let degree = in_degree.get_mut(&next).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push(Reverse(next));
+            }
+        }
+    }
+
+    if order.len() == in_degree.len() {
+        Some(order)
+    } else {
+        None
+    }
+}
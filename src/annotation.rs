@@ -0,0 +1,273 @@
+use std::io::{self, BufRead, Write};
+
+use bstr::{BString, ByteSlice};
+use fnv::FnvHashSet;
+
+use crate::{
+    algorithms::liftover::steps_over,
+    handle::{Handle, NodeId},
+    handlegraph::{HandleGraphRef, HandleSequences},
+    pathgraph::PathHandleGraph,
+};
+
+/// An interval on an embedded path, with 0-based, half-open coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval {
+    pub path_name: BString,
+    pub start: usize,
+    pub end: usize,
+    pub name: BString,
+}
+
+/// The part of an interval lying on a single handle, with 0-based,
+/// half-open offsets along the handle in the orientation the path
+/// crosses it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInterval {
+    pub handle: Handle,
+    pub start: usize,
+    pub end: usize,
+    pub name: BString,
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+fn parse_coord(field: Option<&[u8]>, line: usize) -> io::Result<usize> {
+    field
+        .and_then(|f| f.to_str().ok())
+        .and_then(|f| f.parse::<usize>().ok())
+        .ok_or_else(|| invalid_data(line, "invalid coordinate"))
+}
+
+/// Returns true if the line starts with the word followed by whitespace,
+/// or is only the word
+fn starts_with_word(line: &[u8], word: &[u8]) -> bool {
+    line.starts_with(word) && (line.len() == word.len() || line[word.len()].is_ascii_whitespace())
+}
+
+/// Function that reads the intervals of a BED file, where the
+/// chromosome column holds the name of the path.\
+/// Header, track and browser lines are skipped, and the interval name is
+/// left empty if the file has only 3 columns.
+/// # Example
+/// ```ignore
+/// let file = std::fs::File::open("./genes.bed")?;
+/// let intervals = read_bed(std::io::BufReader::new(file))?;
+/// ```
+pub fn read_bed<R: BufRead>(reader: R) -> io::Result<Vec<Interval>> {
+    let mut intervals = vec![];
+
+    for (ix, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_end_with(|c| c == '\r');
+        if line.is_empty()
+            || line.starts_with(b"#")
+            || starts_with_word(line, b"track")
+            || starts_with_word(line, b"browser")
+        {
+            continue;
+        }
+
+        let mut fields = line.split_str("\t");
+        let path_name = fields.next().unwrap();
+        let start = parse_coord(fields.next(), ix + 1)?;
+        let end = parse_coord(fields.next(), ix + 1)?;
+        let name = fields.next().unwrap_or(b"");
+
+        if start > end {
+            return Err(invalid_data(ix + 1, "interval start after its end"));
+        }
+
+        intervals.push(Interval {
+            path_name: path_name.into(),
+            start,
+            end,
+            name: name.into(),
+        });
+    }
+
+    Ok(intervals)
+}
+
+/// Function that reads the features of a GFF3 file, where the sequence
+/// ID column holds the name of the path.\
+/// Coordinates are converted to 0-based, half-open intervals, and the
+/// name of each interval is taken from the `ID` attribute, then the
+/// `Name` attribute, and falls back to the feature type.
+pub fn read_gff<R: BufRead>(reader: R) -> io::Result<Vec<Interval>> {
+    let mut intervals = vec![];
+
+    for (ix, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_end_with(|c| c == '\r');
+        if line.starts_with(b"##FASTA") {
+            break;
+        }
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+
+        let fields: Vec<&[u8]> = line.split_str("\t").collect();
+        if fields.len() < 9 {
+            return Err(invalid_data(ix + 1, "expected 9 columns"));
+        }
+        let start = parse_coord(Some(fields[3]), ix + 1)?;
+        let end = parse_coord(Some(fields[4]), ix + 1)?;
+        if start == 0 || start > end {
+            return Err(invalid_data(ix + 1, "invalid feature coordinates"));
+        }
+
+        let attribute = |key: &[u8]| {
+            fields[8]
+                .split_str(";")
+                .filter_map(|attr| {
+                    let mut kv = attr.trim().splitn_str(2, "=");
+                    Some((kv.next()?, kv.next()?))
+                })
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v)
+        };
+        let name = attribute(b"ID")
+            .or_else(|| attribute(b"Name"))
+            .unwrap_or(fields[2]);
+
+        intervals.push(Interval {
+            path_name: fields[0].into(),
+            start: start - 1,
+            end,
+            name: name.into(),
+        });
+    }
+
+    Ok(intervals)
+}
+
+/// Function that projects an interval on its path to the handles the
+/// path crosses, using `step_at_position` to find the first step.
+/// Returns None if the path doesn't exist, and leaves out the part of
+/// the interval past the end of the path.
+pub fn project_interval<G>(graph: &G, interval: &Interval) -> Option<Vec<NodeInterval>>
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let path = graph.name_to_path_handle(&interval.path_name)?;
+
+    let projected = steps_over(graph, &path, interval.start..interval.end)
+        .into_iter()
+        .map(|(handle, start, piece)| NodeInterval {
+            handle,
+            start: piece.start - start,
+            end: piece.end - start,
+            name: interval.name.clone(),
+        })
+        .collect();
+
+    Some(projected)
+}
+
+/// Function that projects all the intervals to the handles, skipping
+/// intervals on paths that don't exist
+pub fn project_intervals<G>(graph: &G, intervals: &[Interval]) -> Vec<NodeInterval>
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    intervals
+        .iter()
+        .filter_map(|interval| project_interval(graph, interval))
+        .flatten()
+        .collect()
+}
+
+/// Function that writes the projected intervals as a table sorted by
+/// node, with the columns: node ID, orientation, start, end and name.
+/// # Example
+/// ```ignore
+/// let projected = project_intervals(&graph, &read_bed(reader)?);
+/// write_node_table(std::io::stdout(), &projected)?;
+///
+/// /*
+/// 11  +   2   5   BRCA1
+/// 12  -   0   6   BRCA1
+/// */
+/// ```
+pub fn write_node_table<W: Write>(mut writer: W, intervals: &[NodeInterval]) -> io::Result<()> {
+    let mut sorted: Vec<&NodeInterval> = intervals.iter().collect();
+    sorted.sort_by_key(|i| (i.handle.id(), i.start, i.end));
+
+    for i in sorted {
+        let orient = if i.handle.is_reverse() { "-" } else { "+" };
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}",
+            i.handle.id(),
+            orient,
+            i.start,
+            i.end,
+            i.name
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Function that projects a set of nodes back to a path, returning the
+/// intervals of the path that cross the nodes, merging consecutive
+/// steps. Returns None if the path doesn't exist.
+/// # Example
+/// ```ignore
+/// let nodes: FnvHashSet<NodeId> = bubble.contained.iter().copied().collect();
+/// let intervals = nodes_to_intervals(&graph, b"GRCh38#chr1", &nodes, b"bubble").unwrap();
+/// write_bed(std::io::stdout(), &intervals)?;
+/// ```
+pub fn nodes_to_intervals<G>(
+    graph: &G,
+    path_name: &[u8],
+    nodes: &FnvHashSet<NodeId>,
+    name: &[u8],
+) -> Option<Vec<Interval>>
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let path = graph.name_to_path_handle(path_name)?;
+    let mut intervals: Vec<Interval> = vec![];
+
+    let mut pos = 0;
+    for step in graph.steps_iter(&path) {
+        let handle = graph.handle_of_step(&step)?;
+        let len = graph.node_len(handle);
+        if nodes.contains(&handle.id()) {
+            match intervals.last_mut() {
+                Some(last) if last.end == pos => last.end += len,
+                _ => intervals.push(Interval {
+                    path_name: path_name.into(),
+                    start: pos,
+                    end: pos + len,
+                    name: name.into(),
+                }),
+            }
+        }
+        pos += len;
+    }
+
+    Some(intervals)
+}
+
+/// Function that writes the intervals as a BED file with 4 columns
+pub fn write_bed<W: Write>(mut writer: W, intervals: &[Interval]) -> io::Result<()> {
+    for i in intervals {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            i.path_name, i.start, i.end, i.name
+        )?;
+    }
+    Ok(())
+}
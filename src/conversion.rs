@@ -1,3 +1,5 @@
+#[cfg(feature = "rayon")]
+use crate::hashgraph::GraphBuilder;
 use crate::{
     handle::{Edge, Handle, NodeId},
     hashgraph::HashGraph,
@@ -450,6 +452,33 @@ where
     graph
 }
 
+/// What a GFA line adds to a graph, shared by `fill_gfa_lines` and
+/// `par_fill_gfa_lines`
+enum GfaItem {
+    Node(NodeId, BString),
+    Edge(Edge),
+    Path(BString, Vec<Handle>),
+}
+
+fn gfa_item<T: OptFields>(line: Line1<usize, T>) -> Option<GfaItem> {
+    match line {
+        Line1::Segment(v) => Some(GfaItem::Node(NodeId::from(v.name), v.sequence)),
+        Line1::Link(v) => {
+            let left = Handle::new(v.from_segment, v.from_orient);
+            let right = Handle::new(v.to_segment, v.to_orient);
+            Some(GfaItem::Edge(Edge(left, right)))
+        }
+        Line1::Path(v) => {
+            let steps = v
+                .iter()
+                .map(|(seg, orient)| Handle::new(seg, orient))
+                .collect();
+            Some(GfaItem::Path(v.path_name, steps))
+        }
+        _ => None,
+    }
+}
+
 pub fn fill_gfa_lines<G, I, T>(graph: &mut G, gfa_lines: I) -> GFAResult<()>
 where
     G: AdditiveHandleGraph + PathHandleGraph,
@@ -457,32 +486,64 @@ where
     T: OptFields,
 {
     for line in gfa_lines {
-        let line = line?;
-        match line {
-            Line1::Segment(v) => {
-                let id = NodeId::from(v.name);
-                graph.create_handle(&v.sequence, id);
+        match gfa_item(line?) {
+            Some(GfaItem::Node(id, sequence)) => {
+                graph.create_handle(&sequence, id);
             }
-            Line1::Link(v) => {
-                let left = Handle::new(v.from_segment, v.from_orient);
-                let right = Handle::new(v.to_segment, v.to_orient);
-                graph.create_edge(Edge(left, right));
+            Some(GfaItem::Edge(edge)) => {
+                graph.create_edge(edge);
             }
-            Line1::Path(v) => {
-                let name = &v.path_name;
-                let path_id = graph.create_path_handle(name, false);
-                for (seg, orient) in v.iter() {
-                    let handle = Handle::new(seg, orient);
+            Some(GfaItem::Path(name, steps)) => {
+                let path_id = graph.create_path_handle(&name, false);
+                for handle in steps {
                     graph.append_step(&path_id, handle);
                 }
             }
-            _ => (),
+            None => (),
         }
     }
 
     Ok(())
 }
 
+/// Function that fills a `GraphBuilder` with the lines of a GFA from
+/// many threads, the lines being parsed in parallel too if the iterator
+/// parses them lazily. The graph is then obtained with `finish`.\
+/// Unlike `fill_gfa_lines`, that numbers the paths in the order of the
+/// file, `finish` gives the paths their ids in name order.
+/// # Example
+/// ```ignore
+/// let parser: GFAParser<usize, ()> = GFAParser::new();
+/// let lines = BufReader::new(File::open("./tests/gfa1_files/lil.gfa")?).lines();
+///
+/// let builder = GraphBuilder::new();
+/// par_fill_gfa_lines(
+///     &builder,
+///     lines.par_bridge().map(|l| parser.parse_gfa_line(l.unwrap().as_bytes())),
+/// )?;
+/// let graph = builder.finish().unwrap();
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_fill_gfa_lines<I, T>(builder: &GraphBuilder, gfa_lines: I) -> GFAResult<()>
+where
+    I: rayon::iter::IntoParallelIterator<Item = GFAResult<Line1<usize, T>>>,
+    T: OptFields + Send,
+{
+    use rayon::iter::ParallelIterator;
+
+    gfa_lines.into_par_iter().try_for_each(|line| {
+        match gfa_item(line?) {
+            Some(GfaItem::Node(id, sequence)) => {
+                builder.add_node(id, &sequence);
+            }
+            Some(GfaItem::Edge(edge)) => builder.add_edge(edge),
+            Some(GfaItem::Path(name, steps)) => builder.add_path(&name, false, steps),
+            None => (),
+        }
+        Ok(())
+    })
+}
+
 /// Function that takes a HashGraph object as input and return a GFA object
 /// This function is still ```Work In Progress``` so it's not perfect.\
 /// Sometimes can leads to unexpected bugs.
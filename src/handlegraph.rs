@@ -1,8 +1,12 @@
 use crate::handle::{Direction, Edge, Handle, NodeId};
 
 pub mod iter;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 
 pub use self::iter::*;
+#[cfg(feature = "rayon")]
+pub use self::parallel::*;
 
 /// Access all the handles in the graph as an iterator, and related
 /// methods.
@@ -35,8 +35,8 @@ where
 }
 
 /// Utility struct for iterating through the edges of a single handle,
-/// for use with EdgesIter
-struct HandleEdgesIter<I>
+/// for use with EdgesIter and its parallel counterpart
+pub struct HandleEdgesIter<I>
 where
     I: Iterator<Item = Handle>,
 {
@@ -49,7 +49,7 @@ impl<I> HandleEdgesIter<I>
 where
     I: Iterator<Item = Handle>,
 {
-    fn new(handle: Handle, left: I, right: I) -> Self {
+    pub fn new(handle: Handle, left: I, right: I) -> Self {
         Self {
             handle,
             left_neighbors: Some(left),
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+use fnv::FnvHashMap;
+use rayon::prelude::*;
+
+use crate::handle::{Edge, Handle};
+
+/// Access all the handles in the graph as a parallel iterator, in no
+/// particular order
+pub trait ParallelHandles: Sized {
+    type Handles: ParallelIterator<Item = Handle>;
+
+    fn par_all_handles(self) -> Self::Handles;
+}
+
+/// Access all the edges in the graph as a parallel iterator, in no
+/// particular order. Each edge is visited once, as with `AllEdges`.
+pub trait ParallelEdges: Sized {
+    type Edges: ParallelIterator<Item = Edge>;
+
+    fn par_all_edges(self) -> Self::Edges;
+}
+
+/// Access the paths of the graph, and the steps of a path, as parallel
+/// iterators. Steps are yielded as the handles they cross, and keep the
+/// order of the path when collected.
+pub trait ParallelPaths: Sized {
+    type PathHandle: Send;
+    type Paths: ParallelIterator<Item = Self::PathHandle>;
+    type Steps: IndexedParallelIterator<Item = Handle>;
+
+    fn par_paths(self) -> Self::Paths;
+
+    fn par_steps(self, path: &Self::PathHandle) -> Self::Steps;
+}
+
+/// Function that collects a parallel iterator in sorted order, so that
+/// the result doesn't depend on how the work was split between threads
+/// # Example
+/// ```ignore
+/// // the ids of the nodes longer than 1kb, always in the same order
+/// let long = par_collect_sorted(
+///     graph
+///         .par_all_handles()
+///         .filter(|h| graph.node_len(*h) > 1000)
+///         .map(|h| h.id()),
+/// );
+/// ```
+pub fn par_collect_sorted<I>(iter: I) -> Vec<I::Item>
+where
+    I: ParallelIterator,
+    I::Item: Ord + Send,
+{
+    let mut items: Vec<I::Item> = iter.collect();
+    items.par_sort_unstable();
+    items
+}
+
+/// Function that combines the values of each key with `op`, returning
+/// the keys in sorted order. The result is deterministic as long as `op`
+/// is associative and commutative, such as a sum, a minimum or a union.
+/// # Example
+/// ```ignore
+/// // how many handles have each number of right neighbours
+/// let degrees = par_reduce_by_key(
+///     graph.par_all_handles().map(|h| (graph.degree(h, Direction::Right), 1)),
+///     |a, b| a + b,
+/// );
+/// ```
+pub fn par_reduce_by_key<I, K, V, F>(iter: I, op: F) -> BTreeMap<K, V>
+where
+    I: ParallelIterator<Item = (K, V)>,
+    K: Ord + Hash + Eq + Send,
+    V: Send,
+    F: Fn(V, V) -> V + Send + Sync,
+{
+    let merge = |mut acc: FnvHashMap<K, V>, (k, v): (K, V)| {
+        let v = match acc.remove(&k) {
+            Some(prev) => op(prev, v),
+            None => v,
+        };
+        acc.insert(k, v);
+        acc
+    };
+    let reduced = iter
+        .fold(FnvHashMap::default, &merge)
+        .reduce(FnvHashMap::default, |a, b| {
+            let (small, large) = if a.len() < b.len() { (a, b) } else { (b, a) };
+            small.into_iter().fold(large, &merge)
+        });
+    reduced.into_iter().collect()
+}
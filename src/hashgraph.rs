@@ -8,13 +8,23 @@ use crate::{
     pathgraph::PathHandleGraph,
 };
 
+pub mod builder;
 pub mod graph;
+pub mod history;
 pub mod node;
+pub mod observer;
+#[cfg(feature = "rayon")]
+mod parallel;
 pub mod path;
+pub mod validate;
 
+pub use self::builder::{BuildError, GraphBuilder};
 pub use self::graph::HashGraph;
+pub use self::history::{read_log, Edit, RecordedGraph, Transaction};
 pub use self::node::Node;
+pub use self::observer::{GraphEvent, GraphObserver, ObserverId};
 pub use self::path::{Path, PathId, PathStep};
+pub use self::validate::Violation;
 
 impl<'a> AllHandles for &'a HashGraph {
     type Handles = NodeIdRefHandles<'a, std::collections::hash_map::Keys<'a, NodeId, Node>>;
@@ -124,7 +134,20 @@ impl ModdableHandleGraph for HashGraph {
                 true
             } else {
                 // update the sequence value of node
-                *self.graph.get_mut(&node_id).unwrap().sequence = seq.to_vec();
+                let node = self.graph.get_mut(&node_id).unwrap();
+                let len_changed = node.sequence.len() != seq.len();
+                *node.sequence = seq.to_vec();
+                // the positions of the steps after the node have moved
+                if len_changed {
+                    let path_ids: Vec<PathId> = node.occurrences.keys().copied().collect();
+                    for path_id in path_ids {
+                        let path = self.paths.get_mut(&path_id).unwrap();
+                        if path.is_indexed() {
+                            path.index_positions(&self.graph);
+                        }
+                    }
+                }
+                self.emit(GraphEvent::NodeResequenced(node_id));
                 true
             }
         } else {
@@ -177,90 +200,69 @@ impl ModdableHandleGraph for HashGraph {
 impl SubtractiveHandleGraph for HashGraph {
     fn remove_handle<T: Into<NodeId>>(&mut self, node: T) -> bool {
         let node_id: NodeId = node.into();
-        if self.graph.get(&node_id).is_some() {
-            self.graph.remove(&node_id);
-            // delete all the occurrencies in the edge list of node.id()
-            for handle in self.clone().graph.keys() {
-                self.graph
-                    .get_mut(&handle)
-                    .unwrap()
-                    .left_edges
-                    .retain(|x| x.id() != node_id);
-                self.graph
-                    .get_mut(&handle)
-                    .unwrap()
-                    .right_edges
-                    .retain(|x| x.id() != node_id);
-            }
-            // delete occurrencies of nodeid in path but leaves "holes" in it
-            let mut x: i64 = 0;
-            while self.get_path(&x).is_some() {
-                let nodes = &self.paths.get_mut(&x).unwrap().nodes;
-                if nodes.iter().any(|x| x.id() == node_id) {
-                    self.paths.remove(&x);
-                }
-                x += 1;
-            }
-            true
+        if !self.graph.contains_key(&node_id) {
+            return false;
+        }
+        let edges = if self.has_observers() {
+            self.edges_of(node_id)
         } else {
-            false
+            vec![]
+        };
+        let node = self.graph.remove(&node_id).unwrap();
+        // delete the mirrors of its edges on its neighbors
+        for h in node.left_edges.iter().chain(node.right_edges.iter()) {
+            if let Some(neighbor) = self.graph.get_mut(&h.id()) {
+                neighbor.left_edges.retain(|x| x.id() != node_id);
+                neighbor.right_edges.retain(|x| x.id() != node_id);
+            }
+        }
+        // and the paths crossing it
+        for &path_id in node.occurrences.keys() {
+            self.drop_path(path_id);
         }
+        for edge in edges {
+            self.emit(GraphEvent::EdgeRemoved(edge));
+        }
+        self.emit(GraphEvent::NodeRemoved(node_id));
+        true
     }
 
     fn remove_edge(&mut self, edge: Edge) -> bool {
         let Edge(left, right) = edge;
-        if self.has_edge(left, right) {
-            // delete all the occurrencies of edge found in graph
-            for edges in self.clone().all_edges() {
-                let Edge(l, _) = edges;
-                if edges == edge {
-                    let left_node = self
-                        .graph
-                        .get_mut(&l.id())
-                        .expect("Node doesn't exist for the given handle");
-                    if left.is_reverse() {
-                        let pos = match left_node.left_edges.iter().position(|&h| h == right) {
-                            Some(p) => p,
-                            None => panic!("Error position not found"),
-                        };
-                        self.graph.get_mut(&l.id()).unwrap().left_edges.remove(pos);
-                    } else {
-                        let pos = match left_node.right_edges.iter().position(|&h| h == right) {
-                            Some(p) => p,
-                            None => panic!("Error position not found"),
-                        };
-                        self.graph.get_mut(&l.id()).unwrap().right_edges.remove(pos);
-                    }
-                }
-            }
-            // delete occurrencies of nodeid in path but leaves "holes" in it
-            let mut x: i64 = 0;
-            while self.get_path(&x).is_some() {
-                let nodes = &self.paths.get_mut(&x).unwrap().nodes;
-                if let Some(l) = nodes.iter().position(|x| x.id() == left.id()) {
-                    if let Some(r) = nodes.iter().position(|x| x.id() == right.id()) {
-                        let lr = l + 1;
-                        if lr == r {
-                            self.paths.remove(&x);
-                        }
-                    }
-                }
-                x += 1;
+        // the edge is on the left node, and mirrored on the right one
+        if !self.remove_adjacency(left, right) {
+            return false;
+        }
+        if left != right.flip() {
+            self.remove_adjacency(right.flip(), left.flip());
+        }
+        // delete the paths crossing the edge, which all cross its left node
+        let crossing = Edge::edge_handle(left, right);
+        let path_ids: Vec<PathId> = self.graph[&left.id()].occurrences.keys().copied().collect();
+        for path_id in path_ids {
+            let crosses = match self.paths.get(&path_id) {
+                Some(path) => path
+                    .nodes
+                    .windows(2)
+                    .any(|w| Edge::edge_handle(w[0], w[1]) == crossing),
+                None => false,
+            };
+            if crosses {
+                self.drop_path(path_id);
             }
-            true
-        } else {
-            false
         }
+        self.emit(GraphEvent::EdgeRemoved(crossing));
+        true
     }
 
     fn remove_path(&mut self, name: &[u8]) -> bool {
-        if self.has_path(name) {
-            // delete occurrencies in path leaves "holes"
-            let path_handle = self.name_to_path_handle(name).unwrap();
-            self.paths.remove(&path_handle);
-            true
-        } else {
-            false
+        match self.name_to_path_handle(name) {
+            Some(path_id) => {
+                self.drop_path(path_id);
+                self.path_id.remove(name);
+                true
+            }
+            None => false,
         }
     }
 
@@ -271,6 +273,7 @@ impl SubtractiveHandleGraph for HashGraph {
         self.graph.clear();
         self.path_id.clear();
         self.paths.clear();
+        self.emit(GraphEvent::Cleared);
     }
 }
 
@@ -291,6 +294,7 @@ impl AdditiveHandleGraph for HashGraph {
             self.graph.insert(id, Node::new(seq));
             self.max_id = std::cmp::max(self.max_id, id);
             self.min_id = std::cmp::min(self.min_id, id);
+            self.emit(GraphEvent::NodeCreated(id));
             Handle::pack(id, false)
         }
     }
@@ -328,6 +332,7 @@ impl AdditiveHandleGraph for HashGraph {
                     right_node.left_edges.push(left.flip());
                 }
             }
+            self.emit(GraphEvent::EdgeCreated(Edge::edge_handle(left, right)));
         }
         true
     }
@@ -335,8 +340,16 @@ impl AdditiveHandleGraph for HashGraph {
 
 impl MutableHandleGraph for HashGraph {
     fn divide_handle(&mut self, handle: Handle, mut offsets: Vec<usize>) -> Vec<Handle> {
-        let mut result = vec![handle];
         let node_len = self.node_len(handle);
+        // divide the forward handle at the same places, and return the
+        // pieces in the orientation of the handle
+        if handle.is_reverse() {
+            let fwd_offsets = offsets.iter().rev().map(|o| node_len - o).collect();
+            let pieces = self.divide_handle(handle.forward(), fwd_offsets);
+            return pieces.into_iter().rev().map(|h| h.flip()).collect();
+        }
+
+        let mut result = vec![handle];
         let sequence = self.sequence(handle);
 
         let fwd_handle = handle.forward();
@@ -379,6 +392,12 @@ impl MutableHandleGraph for HashGraph {
             .right_edges;
         // and swap with the new right edges
         std::mem::swap(&mut orig_rights, new_rights);
+        let last = *result.last().unwrap();
+        let moved: Vec<Handle> = self.get_node_unchecked(&last.id()).right_edges.clone();
+        for h in moved {
+            self.emit(GraphEvent::EdgeRemoved(Edge::edge_handle(fwd_handle, h)));
+            self.emit(GraphEvent::EdgeCreated(Edge::edge_handle(last, h)));
+        }
 
         // shrink the sequence of the starting handle
         let orig_node = &mut self.get_node_mut(&handle.id()).unwrap();
@@ -411,22 +430,44 @@ impl MutableHandleGraph for HashGraph {
             self.create_edge(Edge(*this, *next));
         }
 
-        // update paths and path occurrences
-
-        // TODO this is probably not correct, and it's silly to clone
-        // the results all the time
-        let affected_paths: Vec<(i64, usize)> = self
+        // update paths and path occurrences: every step on the node is
+        // replaced by the pieces, in reverse order if the path crosses
+        // the node in reverse
+        let affected_paths: Vec<i64> = self
             .get_node_unchecked(&handle.id())
             .occurrences
-            .iter()
-            .map(|(k, v)| (*k, *v))
+            .keys()
+            .copied()
             .collect();
 
-        for (path_id, ix) in affected_paths.into_iter() {
-            let step = PathStep::Step(path_id, ix);
-            self.rewrite_segment(&step, &step, result.clone());
+        for path_id in affected_paths {
+            let path = self.paths.get_mut(&path_id).unwrap();
+            let mut nodes = Vec::with_capacity(path.nodes.len() + result.len());
+            for &step in path.nodes.iter() {
+                if step.id() != handle.id() {
+                    nodes.push(step);
+                } else if step.is_reverse() {
+                    nodes.extend(result.iter().rev().map(|h| h.flip()));
+                } else {
+                    nodes.extend(result.iter().copied());
+                }
+            }
+            path.nodes = nodes;
+
+            for (ix, step) in path.nodes.iter().enumerate() {
+                let node: &mut Node = self.graph.get_mut(&step.id()).unwrap();
+                node.occurrences.insert(path_id, ix);
+            }
+            if path.is_indexed() {
+                path.index_positions(&self.graph);
+            }
+            self.emit(GraphEvent::PathStepsChanged(path_id));
         }
 
+        self.emit(GraphEvent::NodeDivided {
+            node: handle.id(),
+            pieces: result.clone(),
+        });
         result
     }
 
@@ -434,45 +475,49 @@ impl MutableHandleGraph for HashGraph {
         if !handle.is_reverse() {
             return handle;
         }
+        let node_id = handle.id();
+        // the old reverse handle is the new forward one, and vice versa
+        let flip = |h: Handle| if h.id() == node_id { h.flip() } else { h };
 
-        let node = self.get_node_mut(&handle.id()).unwrap();
-        node.sequence = dna::revcomp(node.sequence.as_slice()).into();
+        // collect every edge on the node, self loops included
+        let edges = self.edges_of(node_id);
 
-        let edges = {
-            let node = self.get_node(&handle.id()).unwrap();
-            node.left_edges
-                .iter()
-                .chain(node.right_edges.iter())
-                .copied()
-                .collect::<Vec<_>>()
-        };
-
-        for target in edges {
-            let other = self.get_node_mut(&target.id()).unwrap();
-            let backward_edges = if target.is_reverse() {
-                other.right_edges.iter_mut()
-            } else {
-                other.left_edges.iter_mut()
-            };
-
-            for backward_handle in backward_edges {
-                if backward_handle.id() == handle.id() {
-                    *backward_handle = backward_handle.flip();
-                    break;
+        // and remove them, along with their mirrors on the neighbours
+        let node = self.get_node_mut(&node_id).unwrap();
+        node.left_edges.clear();
+        node.right_edges.clear();
+        node.sequence = dna::revcomp(node.sequence.as_slice()).into();
+        for Edge(left, right) in edges.iter() {
+            for other in [left.id(), right.id()].iter() {
+                if let Some(n) = self.graph.get_mut(other) {
+                    n.left_edges.retain(|h| h.id() != node_id);
+                    n.right_edges.retain(|h| h.id() != node_id);
                 }
             }
+            self.emit(GraphEvent::EdgeRemoved(Edge(*left, *right)));
         }
+        self.emit(GraphEvent::NodeResequenced(node_id));
 
-        let node = self.get_node_mut(&handle.id()).unwrap();
-        std::mem::swap(&mut node.left_edges, &mut node.right_edges);
-
-        let occurrences = &self.graph.get(&handle.id()).unwrap().occurrences;
-        let paths = &mut self.paths;
+        for Edge(left, right) in edges {
+            let (left, right) = (flip(left), flip(right));
+            if !self.has_edge(left, right) {
+                self.create_edge(Edge(left, right));
+            }
+        }
 
-        for (path_id, index) in occurrences.iter() {
-            let path = paths.get_mut(&path_id).unwrap();
-            let step = path.nodes.get_mut(*index).unwrap();
-            *step = step.flip();
+        // flip every step on the node, a path can cross it more than once
+        let path_ids: Vec<PathId> = self
+            .get_node_unchecked(&node_id)
+            .occurrences
+            .keys()
+            .copied()
+            .collect();
+        for path_id in path_ids {
+            let path = self.paths.get_mut(&path_id).unwrap();
+            for step in path.nodes.iter_mut().filter(|h| h.id() == node_id) {
+                *step = step.flip();
+            }
+            self.emit(GraphEvent::PathStepsChanged(path_id));
         }
 
         handle.flip()
@@ -598,23 +643,31 @@ impl PathHandleGraph for HashGraph {
             node.occurrences.remove(path);
         }
         self.paths.remove(&path);
+        self.emit(GraphEvent::PathRemoved(*path));
     }
 
     fn create_path_handle(&mut self, name: &[u8], is_circular: bool) -> Self::PathHandle {
-        let path_id = self.paths.len() as i64;
+        let mut path_id = self.next_path_id;
+        while self.paths.contains_key(&path_id) {
+            path_id += 1;
+        }
+        self.next_path_id = path_id + 1;
         let path = Path::new(name, path_id, is_circular);
         self.path_id.insert(name.into(), path_id);
         self.paths.insert(path_id, path);
+        self.emit(GraphEvent::PathCreated(path_id));
         path_id
     }
 
     fn append_step(&mut self, path_id: &Self::PathHandle, to_append: Handle) -> Self::StepHandle {
         let path: &mut Path = self.paths.get_mut(path_id).unwrap();
         path.nodes.push(to_append);
+        path.index_appended(self.graph[&to_append.id()].sequence.len());
         let step = (*path_id, path.nodes.len() - 1);
         let node: &mut Node = self.graph.get_mut(&to_append.id()).unwrap();
         node.occurrences.insert(step.0, step.1);
-        PathStep::Step(*path_id, path.nodes.len() - 1)
+        self.emit(GraphEvent::PathStepsChanged(*path_id));
+        PathStep::Step(*path_id, step.1)
     }
 
     fn prepend_step(&mut self, path_id: &Self::PathHandle, to_prepend: Handle) -> Self::StepHandle {
@@ -625,8 +678,10 @@ impl PathHandleGraph for HashGraph {
             *node.occurrences.get_mut(path_id).unwrap() += 1;
         }
         path.nodes.insert(0, to_prepend);
+        path.index_prepended(self.graph[&to_prepend.id()].sequence.len());
         let node: &mut Node = self.graph.get_mut(&to_prepend.id()).unwrap();
         node.occurrences.insert(*path_id, 0);
+        self.emit(GraphEvent::PathStepsChanged(*path_id));
         PathStep::Step(*path_id, 0)
     }
 
@@ -683,6 +738,13 @@ impl PathHandleGraph for HashGraph {
             node.occurrences.insert(path_id, ix);
         }
 
+        // and the position index, if there is one
+        let path = self.paths.get_mut(&path_id).unwrap();
+        if path.is_indexed() {
+            path.index_positions(&self.graph);
+        }
+        self.emit(GraphEvent::PathStepsChanged(path_id));
+
         // return the new beginning and end step handles: even if the
         // input steps were Front and/or End, the output steps exist
         // on the path
@@ -0,0 +1,219 @@
+use std::collections::hash_map::Entry;
+use std::sync::Mutex;
+
+use bstr::{BString, ByteSlice};
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::handle::{Edge, Handle, NodeId};
+
+use super::{HashGraph, Node, Path, PathId};
+
+/// The reasons a `GraphBuilder` can't be turned into a graph. When there
+/// is more than one problem, the smallest node id or path name is
+/// reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Two nodes were added with the same id
+    DuplicateNode(NodeId),
+    /// A node was added with an empty sequence
+    EmptyNode(NodeId),
+    /// An edge or a path step refers to a node that was never added
+    MissingNode(NodeId),
+    /// Two paths were added with the same name
+    DuplicatePath(BString),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use BuildError::*;
+        match self {
+            DuplicateNode(id) => write!(f, "node {} was added more than once", id),
+            EmptyNode(id) => write!(f, "node {} has an empty sequence", id),
+            MissingNode(id) => write!(f, "node {} is used but was never added", id),
+            DuplicatePath(name) => write!(f, "path {} was added more than once", name),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+struct PathRecord {
+    name: BString,
+    is_circular: bool,
+    steps: Vec<Handle>,
+}
+
+/// A graph under construction that many threads can add nodes, edges and
+/// paths to at the same time, in any order: an edge or a path can be
+/// added before the nodes it refers to.\
+/// Nodes and edges are spread over shards, each behind its own lock, so
+/// threads rarely wait for each other. Everything is checked and linked
+/// together by `finish`, which gives the paths their ids in name order.
+/// # Example
+/// ```ignore
+/// let builder = GraphBuilder::new();
+/// lines.par_iter().for_each(|line| match line {
+///     Line::Segment(id, seq) => {
+///         builder.add_node(*id, seq);
+///     }
+///     Line::Link(from, to) => builder.add_edge(Edge(*from, *to)),
+///     Line::Path(name, steps) => builder.add_path(name, false, steps.clone()),
+/// });
+/// let graph: HashGraph = builder.finish()?;
+/// ```
+pub struct GraphBuilder {
+    nodes: Vec<Mutex<FnvHashMap<NodeId, Node>>>,
+    edges: Vec<Mutex<Vec<Edge>>>,
+    paths: Mutex<Vec<PathRecord>>,
+    duplicates: Mutex<Vec<NodeId>>,
+    empty: Mutex<Vec<NodeId>>,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> GraphBuilder {
+        GraphBuilder::with_shards(64)
+    }
+}
+
+impl GraphBuilder {
+    pub fn new() -> GraphBuilder {
+        Default::default()
+    }
+
+    /// Creates a builder with the given number of shards, at least one
+    pub fn with_shards(shards: usize) -> GraphBuilder {
+        let shards = std::cmp::max(shards, 1);
+        GraphBuilder {
+            nodes: (0..shards).map(|_| Mutex::default()).collect(),
+            edges: (0..shards).map(|_| Mutex::default()).collect(),
+            paths: Mutex::default(),
+            duplicates: Mutex::default(),
+            empty: Mutex::default(),
+        }
+    }
+
+    fn shard(&self, id: NodeId) -> usize {
+        (u64::from(id) % self.nodes.len() as u64) as usize
+    }
+
+    /// Adds a node, returning false if a node with the same id was
+    /// already added or if the sequence is empty, which `finish` reports
+    /// as errors
+    pub fn add_node<T: Into<NodeId>>(&self, id: T, sequence: &[u8]) -> bool {
+        let id: NodeId = id.into();
+        if sequence.is_empty() {
+            self.empty.lock().unwrap().push(id);
+            return false;
+        }
+        let added = match self.nodes[self.shard(id)].lock().unwrap().entry(id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(Node::new(sequence));
+                true
+            }
+        };
+        if !added {
+            self.duplicates.lock().unwrap().push(id);
+        }
+        added
+    }
+
+    /// Adds an edge, an edge added more than once is kept once
+    pub fn add_edge(&self, Edge(left, right): Edge) {
+        let edge = Edge::edge_handle(left, right);
+        let Edge(left, _) = edge;
+        self.edges[self.shard(left.id())].lock().unwrap().push(edge);
+    }
+
+    pub fn add_path(&self, name: &[u8], is_circular: bool, steps: Vec<Handle>) {
+        self.paths.lock().unwrap().push(PathRecord {
+            name: name.into(),
+            is_circular,
+            steps,
+        });
+    }
+
+    /// Checks the nodes, edges and paths added, and links them together
+    /// into a graph
+    pub fn finish(self) -> Result<HashGraph, BuildError> {
+        if let Some(id) = self.duplicates.into_inner().unwrap().into_iter().min() {
+            return Err(BuildError::DuplicateNode(id));
+        }
+        if let Some(id) = self.empty.into_inner().unwrap().into_iter().min() {
+            return Err(BuildError::EmptyNode(id));
+        }
+
+        let mut graph = HashGraph::new();
+        for shard in self.nodes {
+            graph.graph.extend(shard.into_inner().unwrap());
+        }
+        graph.min_id = graph.graph.keys().min().copied().unwrap_or(graph.min_id);
+        graph.max_id = graph.graph.keys().max().copied().unwrap_or(graph.max_id);
+
+        let mut edges: Vec<Edge> = vec![];
+        for shard in self.edges {
+            edges.extend(shard.into_inner().unwrap());
+        }
+        edges.sort();
+        edges.dedup();
+
+        let mut paths = self.paths.into_inner().unwrap();
+        paths.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut missing: Option<NodeId> = None;
+        let mut check = |h: &Handle| {
+            if !graph.graph.contains_key(&h.id()) {
+                missing = Some(missing.map_or(h.id(), |m| std::cmp::min(m, h.id())));
+            }
+        };
+        for Edge(left, right) in edges.iter() {
+            check(left);
+            check(right);
+        }
+        for path in paths.iter() {
+            path.steps.iter().for_each(&mut check);
+        }
+        if let Some(id) = missing {
+            return Err(BuildError::MissingNode(id));
+        }
+        let mut names: FnvHashSet<&[u8]> = FnvHashSet::default();
+        for path in paths.iter() {
+            if !names.insert(path.name.as_bytes()) {
+                return Err(BuildError::DuplicatePath(path.name.clone()));
+            }
+        }
+
+        // the same lists `create_edge` would build
+        for Edge(left, right) in edges {
+            let node = graph.graph.get_mut(&left.id()).unwrap();
+            if left.is_reverse() {
+                node.left_edges.push(right);
+            } else {
+                node.right_edges.push(right);
+            }
+            if left != right.flip() {
+                let node = graph.graph.get_mut(&right.id()).unwrap();
+                if right.is_reverse() {
+                    node.right_edges.push(left.flip());
+                } else {
+                    node.left_edges.push(left.flip());
+                }
+            }
+        }
+
+        for (ix, record) in paths.into_iter().enumerate() {
+            let path_id = ix as PathId;
+            let mut path = Path::new(record.name, path_id, record.is_circular);
+            for (step, handle) in record.steps.iter().enumerate() {
+                let node = graph.graph.get_mut(&handle.id()).unwrap();
+                node.occurrences.insert(path_id, step);
+            }
+            path.nodes = record.steps;
+            graph.path_id.insert(path.name.to_vec(), path_id);
+            graph.paths.insert(path_id, path);
+        }
+        graph.next_path_id = graph.paths.len() as PathId;
+
+        Ok(graph)
+    }
+}
@@ -7,13 +7,16 @@ use gfa2::{
 };
 
 use crate::{
-    handle::{Edge as GraphEdge, Handle, NodeId},
+    handle::{Direction, Edge as GraphEdge, Handle, NodeId},
     handlegraph::*,
     mutablehandlegraph::*,
     pathgraph::PathHandleGraph,
 };
 
-use super::{Node, Path, PathId};
+use super::{
+    observer::{GraphEvent, Observers},
+    Node, Path, PathId,
+};
 
 /// New type
 /// # Example
@@ -33,6 +36,10 @@ pub struct HashGraph {
     pub graph: FnvHashMap<NodeId, Node>,
     pub path_id: FnvHashMap<Vec<u8>, i64>,
     pub paths: FnvHashMap<i64, Path>,
+    /// The id the next path created is given, unless it is taken: ids
+    /// of removed paths are never used again
+    pub(crate) next_path_id: PathId,
+    pub(crate) observers: Observers,
 }
 
 impl Default for HashGraph {
@@ -43,6 +50,8 @@ impl Default for HashGraph {
             graph: Default::default(),
             path_id: Default::default(),
             paths: Default::default(),
+            next_path_id: 0,
+            observers: Default::default(),
         }
     }
 }
@@ -507,6 +516,57 @@ impl HashGraph {
         });
     }
 
+    /// Returns the edges on either side of the node, self loops
+    /// included, in the orientation given by `Edge::edge_handle`
+    pub(crate) fn edges_of(&self, node_id: NodeId) -> Vec<GraphEdge> {
+        let fwd = Handle::pack(node_id, false);
+        let mut edges: Vec<GraphEdge> = self
+            .neighbors(fwd, Direction::Right)
+            .map(|h| GraphEdge::edge_handle(fwd, h))
+            .chain(
+                self.neighbors(fwd, Direction::Left)
+                    .map(|h| GraphEdge::edge_handle(h, fwd)),
+            )
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Removes `to` from the edges leaving `from` on its side, returning
+    /// false if it isn't there. The mirror entry on `to` is left as is.
+    pub(crate) fn remove_adjacency(&mut self, from: Handle, to: Handle) -> bool {
+        let node = match self.graph.get_mut(&from.id()) {
+            Some(node) => node,
+            None => return false,
+        };
+        let edges = if from.is_reverse() {
+            &mut node.left_edges
+        } else {
+            &mut node.right_edges
+        };
+        match edges.iter().position(|&h| h == to) {
+            Some(ix) => {
+                edges.remove(ix);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the path along with its occurrences and its name
+    pub(crate) fn drop_path(&mut self, path_id: PathId) {
+        if let Some(path) = self.paths.remove(&path_id) {
+            for handle in path.nodes.iter() {
+                if let Some(node) = self.graph.get_mut(&handle.id()) {
+                    node.occurrences.remove(&path_id);
+                }
+            }
+            self.path_id.remove(path.name.as_slice());
+        }
+        self.emit(GraphEvent::PathRemoved(path_id));
+    }
+
     /// Function that returns a reference to the value corresponding to the key.\
     /// The reference is a Node object wrapped in Option
     /// # Examples
@@ -561,4 +621,191 @@ impl HashGraph {
             .get(path_id)
             .unwrap_or_else(|| panic!("Tried to look up nonexistent path:"))
     }
+
+    /// Function that builds the base position index of a path, so that
+    /// `position_of_step`, `step_at_position` and `path_bases_len` don't
+    /// have to scan the path. The index is kept up to date when the path
+    /// or the sequences of its nodes are modified.
+    /// # Examples
+    /// ```ignore
+    /// let p1 = graph.create_path_handle(b"path-1", false);
+    /// graph.append_step(&p1, h1);
+    /// graph.append_step(&p1, h2);
+    ///
+    /// graph.index_path_positions(&p1);
+    /// // keeps the index up to date
+    /// graph.append_step(&p1, h3);
+    ///
+    /// println!("{:?}", graph.step_at_position(&p1, 1_234));
+    /// ```
+    pub fn index_path_positions(&mut self, path_id: &PathId) -> bool {
+        match self.paths.get_mut(path_id) {
+            Some(path) => {
+                path.index_positions(&self.graph);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Function that builds the base position index of every path
+    pub fn index_all_path_positions(&mut self) {
+        let graph = &self.graph;
+        self.paths
+            .values_mut()
+            .for_each(|path| path.index_positions(graph));
+    }
+
+    /// Function that renumbers the nodes to `1..=n`, rewriting the edges
+    /// and the steps of the paths, and returns the map from the old ids
+    /// to the new ones.\
+    /// The nodes are numbered in the given order, if any, followed by the
+    /// nodes missing from it by increasing id; ids in the order that are
+    /// not in the graph, or repeated, are ignored. Edges and path steps on
+    /// nodes that are not in the graph, which `validate` reports, are
+    /// dropped.
+    /// # Examples
+    /// ```ignore
+    /// let mut graph = HashGraph::new();
+    /// let h1 = graph.create_handle(b"ACCTT", 11);
+    /// let h2 = graph.create_handle(b"TCAAGG", 15);
+    /// graph.create_edge(Edge(h1, h2));
+    ///
+    /// // {11: 1, 15: 2}
+    /// let map = graph.compact_ids(None);
+    /// ```
+    pub fn compact_ids(&mut self, order: Option<&[NodeId]>) -> FnvHashMap<NodeId, NodeId> {
+        let mut sorted: Vec<NodeId> = self.graph.keys().copied().collect();
+        sorted.sort();
+
+        let mut map: FnvHashMap<NodeId, NodeId> = FnvHashMap::default();
+        for &id in order.unwrap_or(&[]).iter().chain(sorted.iter()) {
+            if self.graph.contains_key(&id) && !map.contains_key(&id) {
+                let new_id = NodeId::from(map.len() as u64 + 1);
+                map.insert(id, new_id);
+            }
+        }
+
+        let remap = |h: &Handle| map.get(&h.id()).map(|&id| Handle::pack(id, h.is_reverse()));
+        let old_graph = std::mem::take(&mut self.graph);
+        for (id, mut node) in old_graph {
+            node.left_edges = node.left_edges.iter().filter_map(remap).collect();
+            node.right_edges = node.right_edges.iter().filter_map(remap).collect();
+            self.graph.insert(map[&id], node);
+        }
+        for path in self.paths.values_mut() {
+            let steps = path.nodes.len();
+            path.nodes = path.nodes.iter().filter_map(remap).collect();
+            if path.nodes.len() == steps {
+                continue;
+            }
+            // the steps after a dropped one moved
+            for node in self.graph.values_mut() {
+                node.occurrences.remove(&path.path_id);
+            }
+            for (ix, h) in path.nodes.iter().enumerate() {
+                let node = self.graph.get_mut(&h.id()).unwrap();
+                node.occurrences.insert(path.path_id, ix);
+            }
+            if path.is_indexed() {
+                path.index_positions(&self.graph);
+            }
+        }
+
+        if self.graph.is_empty() {
+            self.min_id = HashGraph::default().min_id;
+            self.max_id = HashGraph::default().max_id;
+        } else {
+            self.min_id = NodeId::from(1);
+            self.max_id = NodeId::from(self.graph.len() as u64);
+        }
+        self.emit(GraphEvent::Reset);
+
+        map
+    }
+
+    /// Function that merges handles spelling the same sequence, in their
+    /// given orientations, into the node of the first one. The edges of
+    /// the other nodes are moved to it and the path steps on them are
+    /// redirected to it, then the other nodes are removed. A node given
+    /// more than once is only merged the first time.\
+    /// Returns the first handle, which now stands for all of them, or None
+    /// if no handles are given.
+    /// # Example
+    /// ```ignore
+    /// // Nodes: 1: ACG, 2: CGT, 3: ACG
+    /// // Edges: 4 -> 1, 5 -> 2-, 3 -> 6
+    /// let merged = graph.merge_handles(&[h1, h2.flip(), h3]);
+    ///
+    /// // Nodes: 1: ACG
+    /// // Edges: 4 -> 1, 5 -> 1, 1 -> 6
+    /// ```
+    pub fn merge_handles(&mut self, handles: &[Handle]) -> Option<Handle> {
+        let keep = *handles.first()?;
+        let sequence = self.sequence(keep);
+
+        let mut merged: Vec<NodeId> = vec![keep.id()];
+        for &other in handles.iter().skip(1) {
+            if other == keep {
+                continue;
+            }
+            if other.id() == keep.id() {
+                panic!("Tried to merge a node with its own reverse");
+            }
+            // the node was already merged
+            if merged.contains(&other.id()) {
+                continue;
+            }
+            merged.push(other.id());
+            if self.sequence(other) != sequence {
+                panic!("Tried to merge handles with different sequences");
+            }
+            let map = |h: Handle| {
+                if h.id() != other.id() {
+                    h
+                } else if h.is_reverse() == other.is_reverse() {
+                    keep
+                } else {
+                    keep.flip()
+                }
+            };
+
+            // move the edges to the merged node, self loops included
+            let edges = self.edges_of(other.id());
+            for &GraphEdge(left, right) in edges.iter() {
+                let (left, right) = (map(left), map(right));
+                if !self.has_edge(left, right) {
+                    self.create_edge(GraphEdge(left, right));
+                }
+            }
+
+            // remove the node along with the mirrors of its edges
+            let node = self.graph.remove(&other.id()).unwrap();
+            for h in node.left_edges.iter().chain(node.right_edges.iter()) {
+                if let Some(n) = self.graph.get_mut(&h.id()) {
+                    n.left_edges.retain(|h| h.id() != other.id());
+                    n.right_edges.retain(|h| h.id() != other.id());
+                }
+            }
+            for edge in edges {
+                self.emit(GraphEvent::EdgeRemoved(edge));
+            }
+            self.emit(GraphEvent::NodeRemoved(other.id()));
+
+            // and redirect the path steps
+            for path_id in node.occurrences.keys() {
+                let path = self.paths.get_mut(path_id).unwrap();
+                for (ix, step) in path.nodes.iter_mut().enumerate() {
+                    if step.id() == other.id() {
+                        *step = map(*step);
+                        let keep_node = self.graph.get_mut(&keep.id()).unwrap();
+                        keep_node.occurrences.insert(*path_id, ix);
+                    }
+                }
+                self.emit(GraphEvent::PathStepsChanged(*path_id));
+            }
+        }
+
+        Some(keep)
+    }
 }
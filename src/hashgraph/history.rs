@@ -0,0 +1,997 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use bstr::{BString, ByteSlice};
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::{HandleNeighbors, HandleSequences},
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{GraphEvent, HashGraph, Node, Path, PathId, PathStep};
+
+/// A mutation of a `HashGraph`, with everything needed to apply it
+/// again on another copy of the graph. Paths are referred to by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    CreateHandle {
+        node: NodeId,
+        sequence: BString,
+    },
+    ModifyHandle {
+        node: NodeId,
+        sequence: BString,
+    },
+    RemoveHandle {
+        node: NodeId,
+    },
+    CreateEdge(Edge),
+    ModifyEdge {
+        edge: Edge,
+        left: Option<Handle>,
+        right: Option<Handle>,
+    },
+    RemoveEdge(Edge),
+    DivideHandle {
+        handle: Handle,
+        offsets: Vec<usize>,
+    },
+    ApplyOrientation(Handle),
+    MergeHandles(Vec<Handle>),
+    CreatePath {
+        path: BString,
+        is_circular: bool,
+    },
+    AppendStep {
+        path: BString,
+        handle: Handle,
+    },
+    /// Replaces the steps from `begin` to `end`, both included
+    RewriteSegment {
+        path: BString,
+        begin: usize,
+        end: usize,
+        segment: Vec<Handle>,
+    },
+    ModifyPath {
+        path: BString,
+        handles: Vec<Handle>,
+    },
+    RemovePath {
+        path: BString,
+    },
+}
+
+/// Whether the field can be written in the log as it is: tabs and line
+/// breaks would split it
+fn plain(field: &[u8]) -> bool {
+    !field.iter().any(|c| matches!(c, b'\t' | b'\n' | b'\r'))
+}
+
+fn done(applied: bool) -> Option<Vec<Handle>> {
+    if applied {
+        Some(vec![])
+    } else {
+        None
+    }
+}
+
+impl Edit {
+    /// The name of the operation, also used in the serialized log
+    pub fn name(&self) -> &'static str {
+        use Edit::*;
+        match self {
+            CreateHandle { .. } => "create_handle",
+            ModifyHandle { .. } => "modify_handle",
+            RemoveHandle { .. } => "remove_handle",
+            CreateEdge(_) => "create_edge",
+            ModifyEdge { .. } => "modify_edge",
+            RemoveEdge(_) => "remove_edge",
+            DivideHandle { .. } => "divide_handle",
+            ApplyOrientation(_) => "apply_orientation",
+            MergeHandles(_) => "merge_handles",
+            CreatePath { .. } => "create_path",
+            AppendStep { .. } => "append_step",
+            RewriteSegment { .. } => "rewrite_segment",
+            ModifyPath { .. } => "modify_path",
+            RemovePath { .. } => "remove_path",
+        }
+    }
+
+    /// Applies the edit to the graph, and returns the handles it
+    /// produced (the new node, the pieces of a divided node...), or None
+    /// if the edit can't be applied to this graph, which is left
+    /// untouched. Sequences and path names with tabs or line breaks are
+    /// refused, as they can't be written in the log.
+    pub fn apply(&self, graph: &mut HashGraph) -> Option<Vec<Handle>> {
+        use Edit::*;
+        let has_node = |graph: &HashGraph, h: &Handle| graph.graph.contains_key(&h.id());
+        let field = match self {
+            CreateHandle { sequence, .. } | ModifyHandle { sequence, .. } => Some(sequence),
+            CreatePath { path, .. }
+            | AppendStep { path, .. }
+            | RewriteSegment { path, .. }
+            | ModifyPath { path, .. }
+            | RemovePath { path } => Some(path),
+            _ => None,
+        };
+        if field.iter().any(|f| !plain(f)) {
+            return None;
+        }
+        match self {
+            CreateHandle { node, sequence } => {
+                if sequence.is_empty() || graph.graph.contains_key(node) {
+                    return None;
+                }
+                Some(vec![graph.create_handle(sequence, *node)])
+            }
+            ModifyHandle { node, sequence } => {
+                done(!sequence.is_empty() && graph.modify_handle(*node, sequence))
+            }
+            RemoveHandle { node } => done(graph.remove_handle(*node)),
+            CreateEdge(Edge(left, right)) => {
+                if !has_node(graph, left) || !has_node(graph, right) {
+                    return None;
+                }
+                if !graph.has_edge(*left, *right) {
+                    graph.create_edge(Edge(*left, *right));
+                }
+                Some(vec![])
+            }
+            ModifyEdge { edge, left, right } => {
+                let Edge(l, r) = edge;
+                let ends = [Some(*l), Some(*r), *left, *right];
+                if !ends.iter().flatten().all(|h| has_node(graph, h)) {
+                    return None;
+                }
+                done(graph.modify_edge(*edge, *left, *right))
+            }
+            RemoveEdge(edge) => {
+                let Edge(l, r) = edge;
+                if !has_node(graph, l) || !has_node(graph, r) {
+                    return None;
+                }
+                done(graph.remove_edge(*edge))
+            }
+            DivideHandle { handle, offsets } => {
+                if offsets.is_empty() || !has_node(graph, handle) {
+                    return None;
+                }
+                let len = graph.node_len(*handle);
+                let mut last = 0;
+                for &o in offsets.iter() {
+                    if o <= last || o >= len {
+                        return None;
+                    }
+                    last = o;
+                }
+                Some(graph.divide_handle(*handle, offsets.clone()))
+            }
+            ApplyOrientation(handle) => {
+                if !has_node(graph, handle) {
+                    return None;
+                }
+                Some(vec![graph.apply_orientation(*handle)])
+            }
+            MergeHandles(handles) => {
+                let first = handles.first()?;
+                if !handles.iter().all(|h| has_node(graph, h)) {
+                    return None;
+                }
+                let sequence = graph.sequence(*first);
+                let valid = handles.iter().all(|h| {
+                    (h.id() != first.id() || h == first) && graph.sequence(*h) == sequence
+                });
+                if !valid {
+                    return None;
+                }
+                Some(vec![graph.merge_handles(handles)?])
+            }
+            CreatePath { path, is_circular } => {
+                if graph.has_path(path) {
+                    return None;
+                }
+                graph.create_path_handle(path, *is_circular);
+                Some(vec![])
+            }
+            AppendStep { path, handle } => {
+                let path = graph.name_to_path_handle(path)?;
+                if !has_node(graph, handle) {
+                    return None;
+                }
+                graph.append_step(&path, *handle);
+                Some(vec![])
+            }
+            RewriteSegment {
+                path,
+                begin,
+                end,
+                segment,
+            } => {
+                let path = graph.name_to_path_handle(path)?;
+                if begin > end
+                    || *end >= graph.step_count(&path)
+                    || !segment.iter().all(|h| has_node(graph, h))
+                {
+                    return None;
+                }
+                let (begin, end) = (PathStep::Step(path, *begin), PathStep::Step(path, *end));
+                graph.rewrite_segment(&begin, &end, segment.clone());
+                Some(vec![])
+            }
+            ModifyPath { path, handles } => {
+                if !handles.iter().all(|h| has_node(graph, h)) {
+                    return None;
+                }
+                done(graph.modify_path(path, handles.clone()))
+            }
+            RemovePath { path } => done(graph.remove_path(path)),
+        }
+    }
+
+    /// The nodes, paths and path names the edit can change, apart from
+    /// the nodes it creates
+    fn footprint(&self, graph: &HashGraph) -> Footprint {
+        use Edit::*;
+        let mut nodes: FnvHashSet<NodeId> = FnvHashSet::default();
+        let mut paths: FnvHashSet<PathId> = FnvHashSet::default();
+        let mut names: FnvHashSet<Vec<u8>> = FnvHashSet::default();
+        // whether the edit changes the edges of the nodes next to its
+        // own, the paths crossing them, and the steps of those paths
+        let (neighbors, crossing, steps) = match self {
+            CreateHandle { node, .. } => {
+                nodes.insert(*node);
+                (false, false, false)
+            }
+            ModifyHandle { node, .. } => {
+                nodes.insert(*node);
+                // only the position index of the paths can change
+                if let Some(node) = graph.graph.get(node) {
+                    let indexed = node
+                        .occurrences
+                        .keys()
+                        .filter(|p| matches!(graph.paths.get(p), Some(path) if path.is_indexed()));
+                    paths.extend(indexed);
+                }
+                (false, false, false)
+            }
+            RemoveHandle { node } => {
+                nodes.insert(*node);
+                // the paths crossing the node are removed
+                (true, true, true)
+            }
+            CreateEdge(Edge(l, r)) => {
+                nodes.extend([l.id(), r.id()].iter());
+                (false, false, false)
+            }
+            RemoveEdge(Edge(l, r)) => {
+                nodes.extend([l.id(), r.id()].iter());
+                // the paths crossing the edge are removed
+                (false, true, true)
+            }
+            ModifyEdge { edge, left, right } => {
+                let Edge(l, r) = edge;
+                let ends = [Some(*l), Some(*r), *left, *right];
+                nodes.extend(ends.iter().flatten().map(|h| h.id()));
+                // as in `RemoveEdge`
+                (false, true, true)
+            }
+            DivideHandle { handle, .. } => {
+                nodes.insert(handle.id());
+                (true, true, true)
+            }
+            ApplyOrientation(handle) => {
+                nodes.insert(handle.id());
+                (true, true, false)
+            }
+            MergeHandles(handles) => {
+                nodes.extend(handles.iter().map(|h| h.id()));
+                (true, true, false)
+            }
+            CreatePath { path, .. } => {
+                names.insert(path.to_vec());
+                (false, false, false)
+            }
+            AppendStep { path, handle } => {
+                names.insert(path.to_vec());
+                nodes.insert(handle.id());
+                (false, false, false)
+            }
+            RewriteSegment { path, segment, .. } => {
+                names.insert(path.to_vec());
+                nodes.extend(segment.iter().map(|h| h.id()));
+                (false, false, true)
+            }
+            ModifyPath { path, handles } => {
+                names.insert(path.to_vec());
+                nodes.extend(handles.iter().map(|h| h.id()));
+                (false, false, true)
+            }
+            RemovePath { path } => {
+                names.insert(path.to_vec());
+                (false, false, true)
+            }
+        };
+
+        for name in names.iter() {
+            paths.extend(graph.path_id.get(name));
+        }
+        let seeds: Vec<NodeId> = nodes.iter().copied().collect();
+        for id in seeds {
+            if let Some(node) = graph.graph.get(&id) {
+                if neighbors {
+                    let edges = node.left_edges.iter().chain(node.right_edges.iter());
+                    nodes.extend(edges.map(|h| h.id()));
+                }
+                if crossing {
+                    paths.extend(node.occurrences.keys());
+                }
+            }
+        }
+        for path_id in paths.iter() {
+            if let Some(path) = graph.paths.get(path_id) {
+                names.insert(path.name.to_vec());
+                if steps {
+                    nodes.extend(path.nodes.iter().map(|h| h.id()));
+                }
+            }
+        }
+
+        Footprint {
+            nodes,
+            paths,
+            names,
+        }
+    }
+}
+
+struct Footprint {
+    nodes: FnvHashSet<NodeId>,
+    paths: FnvHashSet<PathId>,
+    names: FnvHashSet<Vec<u8>>,
+}
+
+fn write_handles(f: &mut fmt::Formatter<'_>, handles: &[Handle]) -> fmt::Result {
+    if handles.is_empty() {
+        return write!(f, "*");
+    }
+    for (ix, h) in handles.iter().enumerate() {
+        if ix > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", HandleStr(*h))?;
+    }
+    Ok(())
+}
+
+struct HandleStr(Handle);
+
+impl fmt::Display for HandleStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orient = if self.0.is_reverse() { '-' } else { '+' };
+        write!(f, "{}{}", self.0.id(), orient)
+    }
+}
+
+/// One edit per line, as tab separated fields: the name of the
+/// operation followed by its arguments. Handles are written as the node
+/// id followed by `+` or `-`, lists are comma separated and `*` stands
+/// for an empty list or a missing handle.
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Edit::*;
+        write!(f, "{}\t", self.name())?;
+        match self {
+            CreateHandle { node, sequence } | ModifyHandle { node, sequence } => {
+                write!(f, "{}\t{}", node, sequence)
+            }
+            RemoveHandle { node } => write!(f, "{}", node),
+            CreateEdge(Edge(l, r)) | RemoveEdge(Edge(l, r)) => {
+                write!(f, "{}\t{}", HandleStr(*l), HandleStr(*r))
+            }
+            ModifyEdge {
+                edge: Edge(l, r),
+                left,
+                right,
+            } => {
+                write!(f, "{}\t{}\t", HandleStr(*l), HandleStr(*r))?;
+                write_handles(f, &left.iter().copied().collect::<Vec<_>>())?;
+                write!(f, "\t")?;
+                write_handles(f, &right.iter().copied().collect::<Vec<_>>())
+            }
+            DivideHandle { handle, offsets } => {
+                let offsets: Vec<String> = offsets.iter().map(|o| o.to_string()).collect();
+                write!(f, "{}\t{}", HandleStr(*handle), offsets.join(","))
+            }
+            ApplyOrientation(handle) => write!(f, "{}", HandleStr(*handle)),
+            MergeHandles(handles) => write_handles(f, handles),
+            CreatePath { path, is_circular } => {
+                write!(f, "{}\t{}", path, if *is_circular { 1 } else { 0 })
+            }
+            AppendStep { path, handle } => write!(f, "{}\t{}", path, HandleStr(*handle)),
+            RewriteSegment {
+                path,
+                begin,
+                end,
+                segment,
+            } => {
+                write!(f, "{}\t{}\t{}\t", path, begin, end)?;
+                write_handles(f, segment)
+            }
+            ModifyPath { path, handles } => {
+                write!(f, "{}\t", path)?;
+                write_handles(f, handles)
+            }
+            RemovePath { path } => write!(f, "{}", path),
+        }
+    }
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+fn parse_number<T: std::str::FromStr>(field: &[u8], line: usize) -> io::Result<T> {
+    field
+        .to_str()
+        .ok()
+        .and_then(|f| f.parse::<T>().ok())
+        .ok_or_else(|| invalid_data(line, "invalid number"))
+}
+
+fn parse_handle(field: &[u8], line: usize) -> io::Result<Handle> {
+    let (id, orient) = match field.split_last() {
+        Some((b'+', id)) => (id, false),
+        Some((b'-', id)) => (id, true),
+        _ => return Err(invalid_data(line, "invalid handle")),
+    };
+    Ok(Handle::pack(parse_number::<u64>(id, line)?, orient))
+}
+
+fn parse_handles(field: &[u8], line: usize) -> io::Result<Vec<Handle>> {
+    if field == b"*" {
+        return Ok(vec![]);
+    }
+    field
+        .split_str(",")
+        .map(|h| parse_handle(h, line))
+        .collect()
+}
+
+fn parse_edit(line: &[u8], ix: usize) -> io::Result<Edit> {
+    use Edit::*;
+    let fields: Vec<&[u8]> = line.split_str("\t").collect();
+    let field = |i: usize| {
+        fields
+            .get(i)
+            .copied()
+            .ok_or_else(|| invalid_data(ix, "missing field"))
+    };
+    let name = |i: usize| field(i).map(BString::from);
+    let optional = |i: usize| -> io::Result<Option<Handle>> {
+        Ok(parse_handles(field(i)?, ix)?.first().copied())
+    };
+
+    let edit = match field(0)? {
+        b"create_handle" => CreateHandle {
+            node: NodeId::from(parse_number::<u64>(field(1)?, ix)?),
+            sequence: name(2)?,
+        },
+        b"modify_handle" => ModifyHandle {
+            node: NodeId::from(parse_number::<u64>(field(1)?, ix)?),
+            sequence: name(2)?,
+        },
+        b"remove_handle" => RemoveHandle {
+            node: NodeId::from(parse_number::<u64>(field(1)?, ix)?),
+        },
+        b"create_edge" => CreateEdge(Edge(
+            parse_handle(field(1)?, ix)?,
+            parse_handle(field(2)?, ix)?,
+        )),
+        b"modify_edge" => ModifyEdge {
+            edge: Edge(parse_handle(field(1)?, ix)?, parse_handle(field(2)?, ix)?),
+            left: optional(3)?,
+            right: optional(4)?,
+        },
+        b"remove_edge" => RemoveEdge(Edge(
+            parse_handle(field(1)?, ix)?,
+            parse_handle(field(2)?, ix)?,
+        )),
+        b"divide_handle" => DivideHandle {
+            handle: parse_handle(field(1)?, ix)?,
+            offsets: field(2)?
+                .split_str(",")
+                .map(|o| parse_number(o, ix))
+                .collect::<io::Result<_>>()?,
+        },
+        b"apply_orientation" => ApplyOrientation(parse_handle(field(1)?, ix)?),
+        b"merge_handles" => MergeHandles(parse_handles(field(1)?, ix)?),
+        b"create_path" => CreatePath {
+            path: name(1)?,
+            is_circular: field(2)? == b"1",
+        },
+        b"append_step" => AppendStep {
+            path: name(1)?,
+            handle: parse_handle(field(2)?, ix)?,
+        },
+        b"rewrite_segment" => RewriteSegment {
+            path: name(1)?,
+            begin: parse_number(field(2)?, ix)?,
+            end: parse_number(field(3)?, ix)?,
+            segment: parse_handles(field(4)?, ix)?,
+        },
+        b"modify_path" => ModifyPath {
+            path: name(1)?,
+            handles: parse_handles(field(2)?, ix)?,
+        },
+        b"remove_path" => RemovePath { path: name(1)? },
+        _ => return Err(invalid_data(ix, "unknown edit")),
+    };
+    Ok(edit)
+}
+
+/// A named group of edits, undone and redone as a whole
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub name: BString,
+    pub edits: Vec<Edit>,
+}
+
+/// The state of the nodes, paths and path names an edit changed, before
+/// and after it
+#[derive(Debug, Clone)]
+struct Delta {
+    nodes: Vec<(NodeId, Option<Node>, Option<Node>)>,
+    paths: Vec<(PathId, Option<Path>, Option<Path>)>,
+    names: Vec<(Vec<u8>, Option<PathId>, Option<PathId>)>,
+    bounds: [(NodeId, NodeId); 2],
+}
+
+fn states<K, V>(map: &FnvHashMap<K, V>, keys: &FnvHashSet<K>) -> Vec<(K, Option<V>, Option<V>)>
+where
+    K: Clone + Eq + std::hash::Hash,
+    V: Clone,
+{
+    keys.iter()
+        .map(|k| (k.clone(), map.get(k).cloned(), None))
+        .collect()
+}
+
+/// Fills in the states after the edit, keeping only the ones it changed
+fn settle<K, V, F>(map: &FnvHashMap<K, V>, states: &mut Vec<(K, Option<V>, Option<V>)>, same: F)
+where
+    K: Clone + Eq + std::hash::Hash,
+    V: Clone,
+    F: Fn(&V, &V) -> bool,
+{
+    for (k, _, after) in states.iter_mut() {
+        *after = map.get(k).cloned();
+    }
+    states.retain(|(_, before, after)| match (before, after) {
+        (Some(b), Some(a)) => !same(b, a),
+        (None, None) => false,
+        _ => true,
+    });
+}
+
+fn restore<K, V>(map: &mut FnvHashMap<K, V>, key: &K, value: &Option<V>)
+where
+    K: Clone + Eq + std::hash::Hash,
+    V: Clone,
+{
+    match value {
+        Some(v) => {
+            map.insert(key.clone(), v.clone());
+        }
+        None => {
+            map.remove(key);
+        }
+    }
+}
+
+impl Delta {
+    /// Takes the state of the part of the graph the edit can change,
+    /// before it is applied
+    fn before(graph: &HashGraph, footprint: &Footprint) -> Delta {
+        Delta {
+            nodes: states(&graph.graph, &footprint.nodes),
+            paths: states(&graph.paths, &footprint.paths),
+            names: states(&graph.path_id, &footprint.names),
+            bounds: [(graph.min_id, graph.max_id); 2],
+        }
+    }
+
+    /// Takes the state after the edit, `created` are the nodes it added.
+    /// The paths it created are the ones its path names now point to.
+    fn after(mut self, graph: &HashGraph, created: &[Handle]) -> Delta {
+        for h in created {
+            if self.nodes.iter().all(|(k, _, _)| *k != h.id()) {
+                self.nodes.push((h.id(), None, None));
+            }
+        }
+        let named: Vec<PathId> = self
+            .names
+            .iter()
+            .filter_map(|(k, _, _)| graph.path_id.get(k).copied())
+            .collect();
+        for id in named {
+            if self.paths.iter().all(|(k, _, _)| *k != id) {
+                self.paths.push((id, None, None));
+            }
+        }
+        settle(&graph.graph, &mut self.nodes, |a, b| a == b);
+        // the position index isn't part of the equality of paths
+        settle(&graph.paths, &mut self.paths, |a, b| {
+            a == b && a.positions() == b.positions()
+        });
+        settle(&graph.path_id, &mut self.names, |a, b| a == b);
+        self.bounds[1] = (graph.min_id, graph.max_id);
+        self
+    }
+
+    /// Sets the graph to the state before the edit if `before` is true,
+    /// to the one after it otherwise
+    fn set(&self, graph: &mut HashGraph, before: bool) {
+        let side = if before { 0 } else { 1 };
+        for (k, old, new) in self.nodes.iter() {
+            restore(&mut graph.graph, k, [old, new][side]);
+        }
+        for (k, old, new) in self.paths.iter() {
+            restore(&mut graph.paths, k, [old, new][side]);
+        }
+        for (k, old, new) in self.names.iter() {
+            restore(&mut graph.path_id, k, [old, new][side]);
+        }
+        let (min_id, max_id) = self.bounds[side];
+        graph.min_id = min_id;
+        graph.max_id = max_id;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    transaction: Transaction,
+    deltas: Vec<Delta>,
+}
+
+/// A `HashGraph` that records every edit applied through it, so that
+/// edits can be undone and redone, grouped in transactions, and written
+/// to a log to replay them on another copy of the graph.\
+/// Every edit stores the state before and after it of the nodes and
+/// paths it changed, found from the ones it names, their neighbors and
+/// the paths crossing them. Undoing, redoing or rolling back edits puts
+/// those states back without the events of the edits: the observers of
+/// the graph are sent `GraphEvent::Reset` instead.
+/// # Example
+/// ```ignore
+/// let mut recorded = RecordedGraph::new(graph);
+/// recorded.begin("fix bubble");
+/// recorded.remove_handle(12);
+/// recorded.create_edge(Edge(h11, h13));
+/// recorded.commit();
+///
+/// recorded.undo();
+/// recorded.redo();
+/// recorded.write_log(&mut std::fs::File::create("./edits.log")?)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordedGraph {
+    graph: HashGraph,
+    done: Vec<Entry>,
+    undone: Vec<Entry>,
+    open: Option<Entry>,
+}
+
+impl RecordedGraph {
+    pub fn new(graph: HashGraph) -> RecordedGraph {
+        RecordedGraph {
+            graph,
+            ..Default::default()
+        }
+    }
+
+    pub fn graph(&self) -> &HashGraph {
+        &self.graph
+    }
+
+    /// Returns the graph, rolling back the open transaction if there is
+    /// one
+    pub fn into_inner(mut self) -> HashGraph {
+        self.rollback();
+        self.graph
+    }
+
+    /// Applies and records an edit, in the open transaction if there is
+    /// one or in a transaction of its own otherwise. Edits that can't be
+    /// applied aren't recorded.
+    pub fn apply(&mut self, edit: Edit) -> Option<Vec<Handle>> {
+        let before = Delta::before(&self.graph, &edit.footprint(&self.graph));
+        let produced = edit.apply(&mut self.graph)?;
+        let delta = before.after(&self.graph, &produced);
+
+        match self.open.as_mut() {
+            Some(entry) => {
+                entry.transaction.edits.push(edit);
+                entry.deltas.push(delta);
+            }
+            None => {
+                self.done.push(Entry {
+                    transaction: Transaction {
+                        name: edit.name().into(),
+                        edits: vec![edit],
+                    },
+                    deltas: vec![delta],
+                });
+            }
+        }
+        self.undone.clear();
+        Some(produced)
+    }
+
+    /// Opens a transaction, that groups every edit until the next
+    /// `commit` or `rollback`. Names with tabs or line breaks are refused,
+    /// as they can't be written in the log, and false is returned.
+    pub fn begin(&mut self, name: &str) -> bool {
+        if self.open.is_some() {
+            panic!("Tried to begin a transaction inside another one");
+        }
+        if !plain(name.as_bytes()) {
+            return false;
+        }
+        self.open = Some(Entry {
+            transaction: Transaction {
+                name: name.into(),
+                edits: vec![],
+            },
+            deltas: vec![],
+        });
+        true
+    }
+
+    /// Closes the open transaction, keeping its edits. Returns false if
+    /// there is no open transaction.
+    pub fn commit(&mut self) -> bool {
+        match self.open.take() {
+            Some(entry) => {
+                if !entry.deltas.is_empty() {
+                    self.done.push(entry);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes the open transaction, reverting its edits. Returns false
+    /// if there is no open transaction.
+    pub fn rollback(&mut self) -> bool {
+        match self.open.take() {
+            Some(entry) => {
+                for delta in entry.deltas.iter().rev() {
+                    delta.set(&mut self.graph, true);
+                }
+                if !entry.deltas.is_empty() {
+                    self.graph.emit(GraphEvent::Reset);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reverts the last committed transaction. Returns false if there is
+    /// nothing to undo, or if a transaction is open.
+    pub fn undo(&mut self) -> bool {
+        if self.open.is_some() {
+            return false;
+        }
+        match self.done.pop() {
+            Some(entry) => {
+                for delta in entry.deltas.iter().rev() {
+                    delta.set(&mut self.graph, true);
+                }
+                self.graph.emit(GraphEvent::Reset);
+                self.undone.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies again the last undone transaction. Returns false if there
+    /// is nothing to redo, or if a transaction is open.
+    pub fn redo(&mut self) -> bool {
+        if self.open.is_some() {
+            return false;
+        }
+        match self.undone.pop() {
+            Some(entry) => {
+                for delta in entry.deltas.iter() {
+                    delta.set(&mut self.graph, false);
+                }
+                self.graph.emit(GraphEvent::Reset);
+                self.done.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The committed transactions that haven't been undone, oldest first
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+        self.done.iter().map(|entry| &entry.transaction)
+    }
+
+    /// Applies and records the transactions, stopping at the first edit
+    /// that can't be applied: its transaction is rolled back and false is
+    /// returned
+    pub fn replay(&mut self, transactions: &[Transaction]) -> bool {
+        for transaction in transactions {
+            if !self.begin(&transaction.name.to_str_lossy()) {
+                return false;
+            }
+            for edit in transaction.edits.iter() {
+                if self.apply(edit.clone()).is_none() {
+                    self.rollback();
+                    return false;
+                }
+            }
+            self.commit();
+        }
+        true
+    }
+
+    /// Writes the committed transactions, each one as a `#` line with its
+    /// name followed by a line for each edit
+    pub fn write_log<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for transaction in self.transactions() {
+            writeln!(writer, "#\t{}", transaction.name)?;
+            for edit in transaction.edits.iter() {
+                writeln!(writer, "{}", edit)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn create_handle<T: Into<NodeId>>(&mut self, seq: &[u8], node_id: T) -> Option<Handle> {
+        let edit = Edit::CreateHandle {
+            node: node_id.into(),
+            sequence: seq.into(),
+        };
+        Some(self.apply(edit)?[0])
+    }
+
+    pub fn append_handle(&mut self, seq: &[u8]) -> Option<Handle> {
+        let node = self.graph.max_id + 1;
+        self.create_handle(seq, node)
+    }
+
+    pub fn modify_handle<T: Into<NodeId>>(&mut self, node_id: T, seq: &[u8]) -> bool {
+        let edit = Edit::ModifyHandle {
+            node: node_id.into(),
+            sequence: seq.into(),
+        };
+        self.apply(edit).is_some()
+    }
+
+    pub fn remove_handle<T: Into<NodeId>>(&mut self, node_id: T) -> bool {
+        let node = node_id.into();
+        self.apply(Edit::RemoveHandle { node }).is_some()
+    }
+
+    pub fn create_edge(&mut self, edge: Edge) -> bool {
+        self.apply(Edit::CreateEdge(edge)).is_some()
+    }
+
+    pub fn modify_edge(&mut self, edge: Edge, left: Option<Handle>, right: Option<Handle>) -> bool {
+        self.apply(Edit::ModifyEdge { edge, left, right }).is_some()
+    }
+
+    pub fn remove_edge(&mut self, edge: Edge) -> bool {
+        self.apply(Edit::RemoveEdge(edge)).is_some()
+    }
+
+    pub fn divide_handle(&mut self, handle: Handle, offsets: Vec<usize>) -> Option<Vec<Handle>> {
+        self.apply(Edit::DivideHandle { handle, offsets })
+    }
+
+    pub fn apply_orientation(&mut self, handle: Handle) -> Option<Handle> {
+        Some(self.apply(Edit::ApplyOrientation(handle))?[0])
+    }
+
+    pub fn merge_handles(&mut self, handles: &[Handle]) -> Option<Handle> {
+        Some(self.apply(Edit::MergeHandles(handles.to_vec()))?[0])
+    }
+
+    pub fn create_path(&mut self, name: &[u8], is_circular: bool) -> bool {
+        let edit = Edit::CreatePath {
+            path: name.into(),
+            is_circular,
+        };
+        self.apply(edit).is_some()
+    }
+
+    pub fn append_step(&mut self, name: &[u8], handle: Handle) -> bool {
+        let edit = Edit::AppendStep {
+            path: name.into(),
+            handle,
+        };
+        self.apply(edit).is_some()
+    }
+
+    pub fn rewrite_segment(
+        &mut self,
+        name: &[u8],
+        begin: usize,
+        end: usize,
+        segment: Vec<Handle>,
+    ) -> bool {
+        let edit = Edit::RewriteSegment {
+            path: name.into(),
+            begin,
+            end,
+            segment,
+        };
+        self.apply(edit).is_some()
+    }
+
+    pub fn modify_path(&mut self, name: &[u8], handles: Vec<Handle>) -> bool {
+        let edit = Edit::ModifyPath {
+            path: name.into(),
+            handles,
+        };
+        self.apply(edit).is_some()
+    }
+
+    pub fn remove_path(&mut self, name: &[u8]) -> bool {
+        self.apply(Edit::RemovePath { path: name.into() }).is_some()
+    }
+}
+
+/// Function that reads a log written by `RecordedGraph::write_log`.
+/// Edits before the first `#` line are grouped in a transaction with an
+/// empty name.
+/// # Example
+/// ```ignore
+/// let file = std::fs::File::open("./edits.log")?;
+/// let log = read_log(std::io::BufReader::new(file))?;
+///
+/// let mut copy = RecordedGraph::new(other_graph);
+/// if !copy.replay(&log) {
+///     println!("the log doesn't apply to this graph");
+/// }
+/// ```
+pub fn read_log<R: BufRead>(reader: R) -> io::Result<Vec<Transaction>> {
+    let mut transactions: Vec<Transaction> = vec![];
+
+    for (ix, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_end_with(|c| c == '\r');
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix(b"#\t") {
+            transactions.push(Transaction {
+                name: name.into(),
+                edits: vec![],
+            });
+            continue;
+        }
+        let edit = parse_edit(line, ix + 1)?;
+        match transactions.last_mut() {
+            Some(transaction) => transaction.edits.push(edit),
+            None => transactions.push(Transaction {
+                name: BString::from(""),
+                edits: vec![edit],
+            }),
+        }
+    }
+
+    Ok(transactions)
+}
@@ -15,7 +15,7 @@ use super::PathId;
 ///     pub occurrences: FnvHashMap<PathId, usize>,
 /// }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     pub sequence: BString,
     pub left_edges: Vec<Handle>,
@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+
+use crate::handle::{Edge, Handle, NodeId};
+
+use super::{HashGraph, PathId};
+
+/// A change made to a `HashGraph` by one of the mutation traits. Edges
+/// are given in the orientation of `Edge::edge_handle`.\
+/// Operations built on simpler ones report the events of those too: a
+/// divided node reports the creation of its new pieces and of the edges
+/// between them before `NodeDivided`. Changes made through the public
+/// fields of the graph are not reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphEvent {
+    NodeCreated(NodeId),
+    NodeRemoved(NodeId),
+    /// The sequence of the node changed, its length may have changed too
+    NodeResequenced(NodeId),
+    /// The node was divided in the pieces, the first one keeping its id
+    NodeDivided {
+        node: NodeId,
+        pieces: Vec<Handle>,
+    },
+    EdgeCreated(Edge),
+    EdgeRemoved(Edge),
+    PathCreated(PathId),
+    PathStepsChanged(PathId),
+    PathRemoved(PathId),
+    /// Everything was removed from the graph
+    Cleared,
+    /// The graph changed in ways the other events don't describe, by
+    /// `compact_ids` or by undoing or redoing the edits of a
+    /// `RecordedGraph`: what was derived from it must be rebuilt
+    Reset,
+}
+
+/// A subscriber to the changes of a `HashGraph`, called after each change
+/// is made
+pub trait GraphObserver: Send + Sync {
+    fn notify(&mut self, event: &GraphEvent);
+}
+
+impl<F> GraphObserver for F
+where
+    F: FnMut(&GraphEvent) + Send + Sync,
+{
+    fn notify(&mut self, event: &GraphEvent) {
+        self(event)
+    }
+}
+
+/// Lets the owner of an index keep a handle to it while the graph
+/// notifies it
+impl<T: GraphObserver> GraphObserver for Arc<Mutex<T>> {
+    fn notify(&mut self, event: &GraphEvent) {
+        if let Ok(mut observer) = self.lock() {
+            observer.notify(event);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+/// The subscribers of a graph. They are not cloned along with it: a copy
+/// of the graph starts with no subscribers.
+#[derive(Default)]
+pub struct Observers {
+    next_id: usize,
+    observers: Vec<(ObserverId, Box<dyn GraphObserver>)>,
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Observers {
+        Observers::default()
+    }
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Observers({})", self.observers.len())
+    }
+}
+
+impl HashGraph {
+    /// Function that subscribes an observer to the changes of the graph,
+    /// and returns the id needed to unsubscribe it. Observers stay with
+    /// the graph they subscribed to: a clone of it starts with none.
+    /// # Example
+    /// ```ignore
+    /// let index = Arc::new(Mutex::new(KmerIndexUpdater::new(&graph)));
+    /// let id = graph.subscribe(Box::new(index.clone()));
+    ///
+    /// graph.modify_handle(12, b"ACGT");
+    /// // the index has been updated
+    /// graph.unsubscribe(id);
+    /// ```
+    pub fn subscribe(&mut self, observer: Box<dyn GraphObserver>) -> ObserverId {
+        let id = ObserverId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers.observers.push((id, observer));
+        id
+    }
+
+    /// Removes the observer with the given id, returning it
+    pub fn unsubscribe(&mut self, id: ObserverId) -> Option<Box<dyn GraphObserver>> {
+        let ix = self
+            .observers
+            .observers
+            .iter()
+            .position(|(i, _)| *i == id)?;
+        Some(self.observers.observers.remove(ix).1)
+    }
+
+    pub(crate) fn has_observers(&self) -> bool {
+        !self.observers.observers.is_empty()
+    }
+
+    pub(crate) fn emit(&mut self, event: GraphEvent) {
+        for (_, observer) in self.observers.observers.iter_mut() {
+            observer.notify(&event);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use rayon::{
+    collections::hash_map,
+    iter::{Copied, FlatMapIter, Map},
+    prelude::*,
+    slice,
+};
+
+use crate::{
+    handle::{Handle, NodeId},
+    handlegraph::{HandleEdgesIter, NeighborIter, ParallelEdges, ParallelHandles, ParallelPaths},
+};
+
+use super::{HashGraph, Node, Path, PathId};
+
+type NodeEdges<'a> = HandleEdgesIter<NeighborIter<'a, std::slice::Iter<'a, Handle>>>;
+
+fn node_handle((id, _): (&NodeId, &Node)) -> Handle {
+    Handle::pack(*id, false)
+}
+
+fn node_edges<'a>((id, node): (&'a NodeId, &'a Node)) -> NodeEdges<'a> {
+    // the same neighbours `HandleNeighbors` gives for the forward handle
+    let left = NeighborIter::new(node.left_edges.iter(), true);
+    let right = NeighborIter::new(node.right_edges.iter(), false);
+    HandleEdgesIter::new(Handle::pack(*id, false), left, right)
+}
+
+fn path_handle((id, _): (&PathId, &Path)) -> PathId {
+    *id
+}
+
+impl<'a> ParallelHandles for &'a HashGraph {
+    type Handles = Map<hash_map::Iter<'a, NodeId, Node>, fn((&'a NodeId, &'a Node)) -> Handle>;
+
+    fn par_all_handles(self) -> Self::Handles {
+        self.graph.par_iter().map(node_handle)
+    }
+}
+
+impl<'a> ParallelEdges for &'a HashGraph {
+    type Edges =
+        FlatMapIter<hash_map::Iter<'a, NodeId, Node>, fn((&'a NodeId, &'a Node)) -> NodeEdges<'a>>;
+
+    fn par_all_edges(self) -> Self::Edges {
+        self.graph.par_iter().flat_map_iter(node_edges)
+    }
+}
+
+impl<'a> ParallelPaths for &'a HashGraph {
+    type PathHandle = PathId;
+    type Paths = Map<hash_map::Iter<'a, PathId, Path>, fn((&'a PathId, &'a Path)) -> PathId>;
+    type Steps = Copied<slice::Iter<'a, Handle>>;
+
+    fn par_paths(self) -> Self::Paths {
+        self.paths.par_iter().map(path_handle)
+    }
+
+    fn par_steps(self, path: &PathId) -> Self::Steps {
+        self.get_path_unchecked(path).nodes.par_iter().copied()
+    }
+}
@@ -36,12 +36,26 @@ impl PathStep {
     }
 }
 
+/// Two paths are equal if they have the same id, name and steps, whether
+/// they have a position index or not
 #[derive(Debug, Clone)]
 pub struct Path {
     pub path_id: PathId,
     pub name: BString,
     pub is_circular: bool,
     pub nodes: Vec<Handle>,
+    /// Optional index of the base position where each step starts,
+    /// with the length of the path as last element
+    positions: Option<Vec<usize>>,
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        self.path_id == other.path_id
+            && self.name == other.name
+            && self.is_circular == other.is_circular
+            && self.nodes == other.nodes
+    }
 }
 
 /*
@@ -119,6 +133,7 @@ impl Path {
             path_id,
             is_circular,
             nodes: vec![],
+            positions: None,
         }
     }
 
@@ -131,12 +146,67 @@ impl Path {
     }
 
     pub fn bases_len(&self, graph: &FnvHashMap<NodeId, Node>) -> usize {
+        if let Some(positions) = &self.positions {
+            return *positions.last().unwrap();
+        }
+
         self.nodes
             .iter()
             .filter_map(|handle| graph.get(&handle.id()).map(|n| n.sequence.len()))
             .sum()
     }
 
+    /// Function that builds the index of the base positions of the
+    /// steps, making `bases_len` constant time and `position_of_step`
+    /// and `step_at_position` logarithmic.\
+    /// The index is kept up to date by the `HashGraph` methods, but it
+    /// must be built again, or dropped with `clear_positions`, if `nodes`
+    /// is modified directly.
+    pub fn index_positions(&mut self, graph: &FnvHashMap<NodeId, Node>) {
+        let mut positions = Vec::with_capacity(self.nodes.len() + 1);
+        let mut bases = 0;
+        positions.push(bases);
+        for handle in self.nodes.iter() {
+            bases += graph
+                .get(&handle.id())
+                .map(|n| n.sequence.len())
+                .unwrap_or(0);
+            positions.push(bases);
+        }
+        self.positions = Some(positions);
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.positions.is_some()
+    }
+
+    /// The index built by `index_positions`, if there is one
+    pub fn positions(&self) -> Option<&[usize]> {
+        self.positions.as_deref()
+    }
+
+    pub fn clear_positions(&mut self) {
+        self.positions = None;
+    }
+
+    /// Updates the index, if there is one, after a step of `len` bases
+    /// was added at the end of the path
+    pub(super) fn index_appended(&mut self, len: usize) {
+        if let Some(positions) = self.positions.as_mut() {
+            let end = positions.last().unwrap() + len;
+            positions.push(end);
+        }
+    }
+
+    /// Updates the index, if there is one, after a step of `len` bases
+    /// was added at the start of the path
+    pub(super) fn index_prepended(&mut self, len: usize) {
+        if let Some(positions) = self.positions.as_mut() {
+            positions.iter_mut().for_each(|p| *p += len);
+            positions.insert(0, 0);
+        }
+    }
+
     pub fn lookup_step_handle(&self, step: &PathStep) -> Option<Handle> {
         match step {
             PathStep::Front(_) => None,
@@ -158,6 +228,10 @@ impl Path {
             PathStep::Front(_) => Some(0),
             PathStep::End(_) => Some(self.bases_len(graph)),
             &PathStep::Step(_, step_ix) => {
+                if let Some(positions) = &self.positions {
+                    return positions.get(step_ix).copied();
+                }
+
                 let mut bases = 0;
                 for handle in self.nodes[0..step_ix].iter() {
                     let node = graph.get(&handle.id())?;
@@ -173,6 +247,16 @@ impl Path {
             return PathStep::Front(self.path_id);
         }
 
+        if let Some(positions) = &self.positions {
+            // the first step ending after the position
+            let ix = positions[1..].partition_point(|&end| end <= pos);
+            return if ix < self.nodes.len() {
+                PathStep::Step(self.path_id, ix)
+            } else {
+                PathStep::End(self.path_id)
+            };
+        }
+
         let mut bases = 0;
         for (ix, handle) in self.nodes.iter().enumerate() {
             let node = graph.get(&handle.id()).unwrap();
@@ -0,0 +1,253 @@
+use crate::handle::{Handle, NodeId};
+
+use super::{HashGraph, PathId};
+
+/// A broken invariant found by `HashGraph::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The node ID is outside of the `min_id..=max_id` range of the graph
+    NodeIdOutOfBounds {
+        node: NodeId,
+        min_id: NodeId,
+        max_id: NodeId,
+    },
+    /// The edge points to a node that is not in the graph
+    DanglingEdge { from: Handle, to: Handle },
+    /// The edge is stored on the `from` node but not on the `to` node
+    UnmirroredEdge { from: Handle, to: Handle },
+    /// The edge is stored more than once on the `from` node
+    DuplicateEdge { from: Handle, to: Handle },
+    /// The step of the path points to a node that is not in the graph
+    MissingStepNode {
+        path: PathId,
+        index: usize,
+        node: NodeId,
+    },
+    /// The node is on the path but its occurrences don't record it
+    MissingOccurrence { path: PathId, node: NodeId },
+    /// The occurrence on the node doesn't match a step of the path on it
+    InvalidOccurrence {
+        node: NodeId,
+        path: PathId,
+        index: usize,
+    },
+    /// The path ID stored in the path differs from its key in `paths`
+    PathIdMismatch { path: PathId, stored: PathId },
+    /// The path name isn't in `path_id`, or it's mapped to another path
+    UnnamedPath { path: PathId },
+    /// The name in `path_id` maps to a missing path, or to a path
+    /// with a different name
+    InvalidPathName { name: Vec<u8>, path: PathId },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use bstr::ByteSlice;
+        use Violation::*;
+
+        let orient = |h: &Handle| if h.is_reverse() { "-" } else { "+" };
+
+        match self {
+            NodeIdOutOfBounds {
+                node,
+                min_id,
+                max_id,
+            } => write!(f, "node {} outside of bounds {}..={}", node, min_id, max_id),
+            DanglingEdge { from, to } => write!(
+                f,
+                "edge {}{} --> {}{} points to a missing node",
+                from.id(),
+                orient(from),
+                to.id(),
+                orient(to)
+            ),
+            UnmirroredEdge { from, to } => write!(
+                f,
+                "edge {}{} --> {}{} is not mirrored on node {}",
+                from.id(),
+                orient(from),
+                to.id(),
+                orient(to),
+                to.id()
+            ),
+            DuplicateEdge { from, to } => write!(
+                f,
+                "edge {}{} --> {}{} is stored more than once",
+                from.id(),
+                orient(from),
+                to.id(),
+                orient(to)
+            ),
+            MissingStepNode { path, index, node } => write!(
+                f,
+                "step {} of path {} points to missing node {}",
+                index, path, node
+            ),
+            MissingOccurrence { path, node } => {
+                write!(f, "node {} has no occurrence of path {}", node, path)
+            }
+            InvalidOccurrence { node, path, index } => write!(
+                f,
+                "occurrence of node {} on step {} of path {} is invalid",
+                node, index, path
+            ),
+            PathIdMismatch { path, stored } => {
+                write!(f, "path {} is stored with ID {}", path, stored)
+            }
+            UnnamedPath { path } => write!(f, "path {} has no entry in the name map", path),
+            InvalidPathName { name, path } => write!(
+                f,
+                "name {} maps to path {} which has a different name",
+                name.as_bstr(),
+                path
+            ),
+        }
+    }
+}
+
+impl HashGraph {
+    /// Function that checks all the invariants of the graph, and returns
+    /// every violation found (an empty vector if the graph is consistent)
+    /// # Example
+    /// ```ignore
+    /// let violations = graph.validate();
+    /// for v in violations.iter() {
+    ///     // edge 1+ --> 2+ is not mirrored on node 2
+    ///     println!("{}", v);
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+
+        let mut node_ids: Vec<NodeId> = self.graph.keys().copied().collect();
+        node_ids.sort();
+
+        for &id in node_ids.iter() {
+            if id < self.min_id || id > self.max_id {
+                violations.push(Violation::NodeIdOutOfBounds {
+                    node: id,
+                    min_id: self.min_id,
+                    max_id: self.max_id,
+                });
+            }
+        }
+
+        for &id in node_ids.iter() {
+            let node = &self.graph[&id];
+            // a handle in the right edges of a node is the target of an
+            // edge leaving the forward handle, and one in the left edges
+            // is the target of an edge leaving the reverse handle
+            let sides = [
+                (Handle::pack(id, false), &node.right_edges),
+                (Handle::pack(id, true), &node.left_edges),
+            ];
+            for (from, edges) in sides.iter() {
+                let from = *from;
+                for (ix, &to) in edges.iter().enumerate() {
+                    if edges[..ix].contains(&to) {
+                        violations.push(Violation::DuplicateEdge { from, to });
+                    }
+                    let other = match self.graph.get(&to.id()) {
+                        Some(other) => other,
+                        None => {
+                            violations.push(Violation::DanglingEdge { from, to });
+                            continue;
+                        }
+                    };
+                    // the mirror of from -> to is to' -> from'
+                    let mirror = if to.is_reverse() {
+                        &other.right_edges
+                    } else {
+                        &other.left_edges
+                    };
+                    if !mirror.contains(&from.flip()) {
+                        violations.push(Violation::UnmirroredEdge { from, to });
+                    }
+                }
+            }
+        }
+
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort();
+
+        for &path_id in path_ids.iter() {
+            let path = &self.paths[&path_id];
+            if path.path_id != path_id {
+                violations.push(Violation::PathIdMismatch {
+                    path: path_id,
+                    stored: path.path_id,
+                });
+            }
+            if self.path_id.get(path.name.as_slice()) != Some(&path_id) {
+                violations.push(Violation::UnnamedPath { path: path_id });
+            }
+
+            for (index, handle) in path.nodes.iter().enumerate() {
+                let node = match self.graph.get(&handle.id()) {
+                    Some(node) => node,
+                    None => {
+                        violations.push(Violation::MissingStepNode {
+                            path: path_id,
+                            index,
+                            node: handle.id(),
+                        });
+                        continue;
+                    }
+                };
+                // a node can only record one occurrence for each path,
+                // so check the first step on the node
+                let first = path.nodes[..index].iter().all(|h| h.id() != handle.id());
+                if first && !node.occurrences.contains_key(&path_id) {
+                    violations.push(Violation::MissingOccurrence {
+                        path: path_id,
+                        node: handle.id(),
+                    });
+                }
+            }
+        }
+
+        for &id in node_ids.iter() {
+            let mut occurrences: Vec<(PathId, usize)> = self.graph[&id]
+                .occurrences
+                .iter()
+                .map(|(p, ix)| (*p, *ix))
+                .collect();
+            occurrences.sort();
+
+            for (path_id, index) in occurrences {
+                let valid = self
+                    .paths
+                    .get(&path_id)
+                    .and_then(|p| p.nodes.get(index))
+                    .map(|h| h.id() == id)
+                    .unwrap_or(false);
+                if !valid {
+                    violations.push(Violation::InvalidOccurrence {
+                        node: id,
+                        path: path_id,
+                        index,
+                    });
+                }
+            }
+        }
+
+        let mut names: Vec<(&Vec<u8>, &PathId)> = self.path_id.iter().collect();
+        names.sort();
+
+        for (name, &path_id) in names {
+            let valid = self
+                .paths
+                .get(&path_id)
+                .map(|p| p.name.as_slice() == name.as_slice())
+                .unwrap_or(false);
+            if !valid {
+                violations.push(Violation::InvalidPathName {
+                    name: name.clone(),
+                    path: path_id,
+                });
+            }
+        }
+
+        violations
+    }
+}
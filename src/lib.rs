@@ -1,3 +1,5 @@
+pub mod algorithms;
+pub mod annotation;
 pub mod conversion;
 pub mod handle;
 pub mod handlegraph;
@@ -5,3 +7,4 @@ pub mod hashgraph;
 pub mod mutablehandlegraph;
 pub mod pathgraph;
 pub mod pathhandlegraph;
+pub mod vcf;
@@ -16,7 +16,9 @@ pub trait SubtractiveHandleGraph {
     /// ```
     fn remove_handle<T: Into<NodeId>>(&mut self, node: T) -> bool;
 
-    /// Function that removes an Edge (Link) between 2 nodes
+    /// Function that removes an Edge (Link) between 2 nodes, from both
+    /// of the nodes, given in either orientation. The paths crossing the
+    /// edge are removed too. Returns false if there is no such edge.
     /// # Example
     /// ```ignore
     /// // Nodes: 11, 12, 13
@@ -31,7 +33,8 @@ pub trait SubtractiveHandleGraph {
     /// ```
     fn remove_edge(&mut self, edge: Edge) -> bool;
 
-    /// Function that removes an Edge (Link) between 2 nodes
+    /// Function that removes a path, along with its occurrences on the
+    /// nodes, so that its name can be used again
     /// # Example
     /// ```ignore
     /// // Nodes: 11, 12, 13
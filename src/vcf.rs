@@ -0,0 +1,754 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+use bio::alphabets::dna;
+use bstr::{BString, ByteSlice};
+use fnv::FnvHashMap;
+
+use crate::{
+    algorithms::{find_bubbles, pansn_sample, MAX_BUBBLE_SIZE},
+    handle::{Edge, Handle, NodeId},
+    handlegraph::{HandleGraphRef, HandleSequences},
+    hashgraph::HashGraph,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+/// A named reference sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contig {
+    pub name: BString,
+    pub sequence: BString,
+}
+
+/// The alleles called for a sample at a record, where None is a missing
+/// allele (`.`). Haploid genotypes count as phased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Genotype {
+    pub alleles: Vec<Option<usize>>,
+    pub phased: bool,
+}
+
+/// A VCF record, with the 1-based position of the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VcfRecord {
+    pub chrom: BString,
+    pub pos: usize,
+    pub id: BString,
+    pub reference: BString,
+    pub alternatives: Vec<BString>,
+    pub info: BString,
+    pub genotypes: Vec<Genotype>,
+}
+
+impl VcfRecord {
+    /// Returns the value of an INFO key, or None if the key is missing
+    /// or is a flag
+    pub fn info_value(&self, key: &[u8]) -> Option<&[u8]> {
+        self.info.split_str(";").find_map(|field| {
+            let mut kv = field.splitn_str(2, "=");
+            if kv.next()? == key {
+                kv.next()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vcf {
+    pub samples: Vec<BString>,
+    pub records: Vec<VcfRecord>,
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+/// Function that reads the sequences of a FASTA file, where the name of
+/// each sequence is the first word of its header
+/// # Example
+/// ```ignore
+/// let file = std::fs::File::open("./reference.fa")?;
+/// let contigs = read_fasta(std::io::BufReader::new(file))?;
+/// ```
+pub fn read_fasta<R: BufRead>(reader: R) -> io::Result<Vec<Contig>> {
+    let mut contigs: Vec<Contig> = vec![];
+
+    for (ix, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_end_with(|c| c == '\r');
+        if line.is_empty() || line.starts_with(b";") {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix(b">") {
+            let name = header.fields().next().unwrap_or(b"");
+            contigs.push(Contig {
+                name: name.into(),
+                sequence: BString::from(""),
+            });
+        } else {
+            let contig = contigs
+                .last_mut()
+                .ok_or_else(|| invalid_data(ix + 1, "sequence before the first header"))?;
+            contig.sequence.extend_from_slice(line.trim());
+        }
+    }
+
+    Ok(contigs)
+}
+
+fn parse_genotype(field: &[u8]) -> Genotype {
+    let phased = !field.contains(&b'/');
+    let alleles = field
+        .split(|&c| c == b'|' || c == b'/')
+        .map(|allele| allele.to_str().ok().and_then(|a| a.parse::<usize>().ok()))
+        .collect();
+    Genotype { alleles, phased }
+}
+
+/// Function that reads the samples and records of a VCF file; only the
+/// GT field of the genotypes is kept, and records without it have no
+/// genotypes
+/// # Example
+/// ```ignore
+/// let file = std::fs::File::open("./variants.vcf")?;
+/// let vcf = read_vcf(std::io::BufReader::new(file))?;
+/// ```
+pub fn read_vcf<R: BufRead>(reader: R) -> io::Result<Vcf> {
+    let mut vcf = Vcf::default();
+
+    for (ix, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_end_with(|c| c == '\r');
+        if line.is_empty() || line.starts_with(b"##") {
+            continue;
+        }
+
+        let fields: Vec<&[u8]> = line.split_str("\t").collect();
+        if line.starts_with(b"#") {
+            vcf.samples = fields.iter().skip(9).map(|&s| s.into()).collect();
+            continue;
+        }
+        if fields.len() < 8 {
+            return Err(invalid_data(ix + 1, "expected at least 8 columns"));
+        }
+
+        let pos = fields[1]
+            .to_str()
+            .ok()
+            .and_then(|p| p.parse::<usize>().ok())
+            .filter(|&p| p > 0)
+            .ok_or_else(|| invalid_data(ix + 1, "invalid position"))?;
+        let alternatives = if fields[4] == b"." {
+            vec![]
+        } else {
+            fields[4].split_str(",").map(BString::from).collect()
+        };
+
+        let gt_index = fields
+            .get(8)
+            .and_then(|format| format.split_str(":").position(|key| key == b"GT"));
+        let genotypes = match gt_index {
+            Some(gt) => fields
+                .iter()
+                .skip(9)
+                .map(|sample| parse_genotype(sample.split_str(":").nth(gt).unwrap_or(b".")))
+                .collect(),
+            None => vec![],
+        };
+
+        vcf.records.push(VcfRecord {
+            chrom: fields[0].into(),
+            pos,
+            id: fields[2].into(),
+            reference: fields[3].into(),
+            alternatives,
+            info: fields[7].into(),
+            genotypes,
+        });
+    }
+
+    Ok(vcf)
+}
+
+/// A record placed on its contig, as the 0-based, half-open interval it
+/// replaces and its alleles with the shared flanks trimmed; the first
+/// allele is the reference one, and unsupported alternative alleles are
+/// None
+struct Site {
+    record: usize,
+    start: usize,
+    end: usize,
+    alleles: Vec<Option<BString>>,
+}
+
+fn place_record(index: usize, record: &VcfRecord, sequence: &[u8]) -> Option<Site> {
+    let start = record.pos - 1;
+    let mut end = start + record.reference.len();
+    if start == end
+        || end > sequence.len()
+        || !sequence[start..end].eq_ignore_ascii_case(&record.reference)
+    {
+        return None;
+    }
+
+    // symbolic deletions and inversions span up to their END
+    let sv_end = record
+        .info_value(b"END")
+        .and_then(|e| e.to_str().ok())
+        .and_then(|e| e.parse::<usize>().ok())
+        .filter(|&e| e > start && e <= sequence.len());
+    for alt in record.alternatives.iter() {
+        if let (b"<DEL>", Some(e)) | (b"<INV>", Some(e)) = (alt.as_slice(), sv_end) {
+            end = end.max(e);
+        }
+    }
+
+    let anchor = &sequence[start..start + 1];
+    let padding = &sequence[start + record.reference.len()..end];
+    let mut alleles: Vec<Option<Vec<u8>>> = vec![Some(sequence[start..end].to_vec())];
+    for alt in record.alternatives.iter() {
+        let allele = match (alt.as_slice(), sv_end) {
+            (b"<DEL>", Some(e)) => Some([anchor, &sequence[e..end]].concat()),
+            (b"<INV>", Some(e)) => {
+                let inverted = dna::revcomp(&sequence[start + 1..e]);
+                Some([anchor, &inverted, &sequence[e..end]].concat())
+            }
+            (alt, _) if alt.iter().all(|c| c.is_ascii_alphabetic()) => {
+                Some([alt, padding].concat())
+            }
+            _ => None,
+        };
+        alleles.push(allele);
+    }
+    if alleles.iter().skip(1).all(|a| a.is_none()) {
+        return None;
+    }
+
+    // trim the flanks shared by all the alleles
+    let present: Vec<&[u8]> = alleles.iter().flatten().map(|a| a.as_slice()).collect();
+    let shortest = present.iter().map(|a| a.len()).min().unwrap();
+    let same = |byte: &dyn Fn(&[u8]) -> u8| {
+        present
+            .iter()
+            .all(|a| byte(a).eq_ignore_ascii_case(&byte(present[0])))
+    };
+    let suffix = (0..shortest)
+        .take_while(|&n| same(&|a| a[a.len() - 1 - n]))
+        .count();
+    let prefix = (0..shortest - suffix)
+        .take_while(|&n| same(&|a| a[n]))
+        .count();
+
+    let alleles: Vec<Option<BString>> = alleles
+        .into_iter()
+        .map(|a| a.map(|a| a[prefix..a.len() - suffix].into()))
+        .collect();
+    // nothing left to vary, e.g. the inversion of a palindrome
+    if alleles.iter().flatten().all(|a| a.is_empty()) {
+        return None;
+    }
+
+    Some(Site {
+        record: index,
+        start: start + prefix,
+        end: end - suffix,
+        alleles,
+    })
+}
+
+/// A graph built from a reference and its variants, with the indices of
+/// the records that were left out
+#[derive(Debug, Clone)]
+pub struct VcfGraph {
+    pub graph: HashGraph,
+    pub skipped: Vec<usize>,
+}
+
+/// Function that builds a variation graph from reference sequences and
+/// the records of a VCF.\
+/// Each reference is cut at the boundaries of its variants, each
+/// alternative allele becomes a node joining the nodes around it, the
+/// alleles of adjacent records included, and each reference is embedded
+/// as a path with its own name. If
+/// `haplotype_paths` is true, each haplotype of each sample is embedded
+/// as well, following the alleles of its phased genotypes (and the
+/// reference elsewhere), as a `sample#haplotype#contig` path.\
+/// SNPs, indels and other sequence-resolved alleles are supported, as
+/// well as `<DEL>` and `<INV>` alleles with an END. Records on unknown
+/// contigs, with a REF not matching the reference, with no supported
+/// allele, or overlapping a previous record are skipped.
+/// # Example
+/// ```ignore
+/// let contigs = read_fasta(BufReader::new(File::open("./reference.fa")?))?;
+/// let vcf = read_vcf(BufReader::new(File::open("./variants.vcf")?))?;
+///
+/// let VcfGraph { graph, skipped } = graph_from_vcf(&contigs, &vcf, true);
+/// ```
+pub fn graph_from_vcf(contigs: &[Contig], vcf: &Vcf, haplotype_paths: bool) -> VcfGraph {
+    let mut graph = HashGraph::new();
+    let contig_index: FnvHashMap<&[u8], usize> = contigs
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.name.as_slice(), i))
+        .collect();
+
+    let mut sites: Vec<Vec<Site>> = contigs.iter().map(|_| vec![]).collect();
+    let mut skipped = vec![];
+    for (index, record) in vcf.records.iter().enumerate() {
+        let site = contig_index
+            .get(record.chrom.as_slice())
+            .and_then(|&c| place_record(index, record, &contigs[c].sequence).map(|site| (c, site)));
+        match site {
+            Some((c, site)) => sites[c].push(site),
+            None => skipped.push(index),
+        }
+    }
+
+    let ploidy: Vec<usize> = (0..vcf.samples.len())
+        .map(|s| {
+            vcf.records
+                .iter()
+                .filter_map(|r| r.genotypes.get(s))
+                .map(|g| g.alleles.len())
+                .max()
+                .unwrap_or(1)
+        })
+        .collect();
+
+    for (contig, mut contig_sites) in contigs.iter().zip(sites) {
+        let sequence = contig.sequence.as_slice();
+
+        // insertions come before the other records at the same position
+        contig_sites.sort_by_key(|s| (s.start, s.start != s.end, s.record));
+        let mut accepted: Vec<Site> = vec![];
+        for site in contig_sites {
+            match accepted.last() {
+                Some(last) if site.start < last.end => skipped.push(site.record),
+                _ => accepted.push(site),
+            }
+        }
+
+        let mut breakpoints: BTreeSet<usize> = BTreeSet::new();
+        breakpoints.insert(0);
+        breakpoints.insert(sequence.len());
+        for site in accepted.iter() {
+            breakpoints.insert(site.start);
+            breakpoints.insert(site.end);
+        }
+        let breakpoints: Vec<usize> = breakpoints.into_iter().collect();
+
+        // the reference node starting at each breakpoint, with its end
+        let mut reference_nodes: FnvHashMap<usize, (Handle, usize)> = FnvHashMap::default();
+        for window in breakpoints.windows(2) {
+            if window[0] < window[1] {
+                let handle = graph.append_handle(&sequence[window[0]..window[1]]);
+                reference_nodes.insert(window[0], (handle, window[1]));
+            }
+        }
+        let reference_walk = |from: usize, to: usize| {
+            let mut walk = vec![];
+            let mut pos = from;
+            while pos < to {
+                let (handle, end) = reference_nodes[&pos];
+                walk.push(handle);
+                pos = end;
+            }
+            walk
+        };
+
+        let allele_nodes: Vec<Vec<Option<Handle>>> = accepted
+            .iter()
+            .map(|site| {
+                site.alleles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, allele)| match allele {
+                        Some(seq) if i > 0 && !seq.is_empty() => Some(graph.append_handle(seq)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // the walk of a haplotype choosing an allele at each site
+        let haplotype_walk = |choice: &dyn Fn(&Site) -> usize| {
+            let mut walk = vec![];
+            let mut pos = 0;
+            for (site, nodes) in accepted.iter().zip(allele_nodes.iter()) {
+                walk.extend(reference_walk(pos, site.start));
+                let allele = choice(site);
+                match site.alleles.get(allele) {
+                    Some(Some(_)) if allele > 0 => walk.extend(nodes[allele]),
+                    _ => walk.extend(reference_walk(site.start, site.end)),
+                }
+                pos = site.end;
+            }
+            walk.extend(reference_walk(pos, sequence.len()));
+            walk
+        };
+
+        let mut walks: Vec<(BString, Vec<Handle>)> =
+            vec![(contig.name.clone(), haplotype_walk(&|_| 0))];
+        if haplotype_paths {
+            for (s, sample) in vcf.samples.iter().enumerate() {
+                for h in 0..ploidy[s] {
+                    let choice = |site: &Site| {
+                        vcf.records[site.record]
+                            .genotypes
+                            .get(s)
+                            .filter(|g| g.phased)
+                            .and_then(|g| g.alleles.get(h).copied().flatten())
+                            .unwrap_or(0)
+                    };
+                    let name = format!("{}#{}#{}", sample, h + 1, contig.name);
+                    walks.push((name.into(), haplotype_walk(&choice)));
+                }
+            }
+        }
+
+        for (name, walk) in walks {
+            for pair in walk.windows(2) {
+                graph.create_edge(Edge(pair[0], pair[1]));
+            }
+            let path = graph.create_path_handle(&name, false);
+            for handle in walk {
+                graph.append_step(&path, handle);
+            }
+        }
+
+        // join every alternative allele to whatever ends where it starts:
+        // the reference, the alleles of an adjacent record, or the bases
+        // before a deletion, and whatever ends at a position to the
+        // reference node starting there
+        let mut ends: FnvHashMap<usize, Vec<Handle>> = FnvHashMap::default();
+        for &(handle, end) in reference_nodes.values() {
+            ends.entry(end).or_default().push(handle);
+        }
+        for (site, nodes) in accepted.iter().zip(allele_nodes.iter()) {
+            let before = ends.get(&site.start).cloned().unwrap_or_default();
+            let mut after = vec![];
+            for (allele, &node) in site.alleles.iter().zip(nodes.iter()).skip(1) {
+                match (allele, node) {
+                    (Some(_), Some(node)) => {
+                        for &prev in before.iter() {
+                            graph.create_edge(Edge(prev, node));
+                        }
+                        after.push(node);
+                    }
+                    (Some(_), None) => after.extend(before.iter().copied()),
+                    (None, _) => (),
+                }
+            }
+            let ending = ends.entry(site.end).or_default();
+            ending.extend(after);
+            ending.sort();
+            ending.dedup();
+        }
+        for (pos, handles) in ends.iter() {
+            if let Some(&(next, _)) = reference_nodes.get(pos) {
+                for &prev in handles.iter() {
+                    graph.create_edge(Edge(prev, next));
+                }
+            }
+        }
+    }
+
+    skipped.sort_unstable();
+    VcfGraph { graph, skipped }
+}
+
+/// Returns the haplotype part of a path name following the PanSN
+/// convention (`sample#haplotype#contig`), or an empty haplotype if the
+/// name doesn't contain a `#`
+fn pansn_haplotype(name: &[u8]) -> &[u8] {
+    name.split(|&c| c == b'#').nth(1).unwrap_or(b"")
+}
+
+/// Returns the handles a path crosses between its steps on two nodes,
+/// oriented from the first node to the second, along with the positions
+/// of the two steps and the handle of the first one. Returns None if the
+/// path doesn't cross both nodes.\
+/// On a node the path crosses more than once, the earliest step among the
+/// ones `occurrences_iter` reports is used. A `HashGraph` only reports one
+/// step for each path on a node, usually the last one added.
+fn walk_between<G>(
+    graph: &G,
+    path: &G::PathHandle,
+    from: NodeId,
+    to: NodeId,
+) -> Option<(usize, usize, Handle, Vec<Handle>)>
+where
+    G: PathHandleGraph,
+    G::PathHandle: PartialEq,
+    G::StepHandle: PartialEq,
+{
+    let step_on = |node: NodeId| {
+        graph
+            .occurrences_iter(Handle::pack(node, false))
+            .filter(|step| graph.path_handle_of_step(step) == *path)
+            .filter_map(|step| Some((graph.position_of_step(&step)?, step)))
+            .min_by_key(|(pos, _)| *pos)
+    };
+    let (from_pos, from_step) = step_on(from)?;
+    let (to_pos, to_step) = step_on(to)?;
+
+    let from_handle = graph.handle_of_step(&from_step)?;
+    let (first, last) = if from_pos <= to_pos {
+        (from_step, to_step)
+    } else {
+        (to_step, from_step)
+    };
+    let mut walk = vec![];
+    let mut step = graph.next_step(&first);
+    while step != last {
+        walk.push(graph.handle_of_step(&step)?);
+        step = graph.next_step(&step);
+    }
+    if from_pos > to_pos {
+        walk = walk.into_iter().rev().map(|h| h.flip()).collect();
+    }
+
+    Some((from_pos, to_pos, from_handle, walk))
+}
+
+/// Function that decomposes a graph into the variants of its paths
+/// against a reference path, with one record for each outermost bubble
+/// whose boundaries lie on the reference and where at least one
+/// haplotype differs from it.\
+/// Every other path is a haplotype of the sample and haplotype given by
+/// its PanSN name (`sample#haplotype#contig`), and the genotypes are
+/// phased, with `.` for the haplotypes not crossing the bubble.
+/// Positions are taken from the reference path, alleles are padded with
+/// the base before the bubble when one of them is empty, and the ID of
+/// each record holds the boundaries of its bubble, e.g. `>1>4`. Bubbles
+/// with more than `MAX_BUBBLE_SIZE` nodes are not looked for, and the
+/// ones the reference path can't be followed through are skipped.\
+/// Returns None if the graph has no path with the reference name.
+/// # Example
+/// ```ignore
+/// let vcf = variants_from_graph(&graph, b"chr1").unwrap();
+/// write_vcf(std::io::stdout(), &vcf)?;
+/// ```
+pub fn variants_from_graph<G>(graph: &G, reference: &[u8]) -> Option<Vcf>
+where
+    G: PathHandleGraph,
+    G::PathHandle: PartialEq,
+    G::StepHandle: PartialEq,
+    for<'a> &'a G: HandleGraphRef,
+{
+    let ref_path = graph.name_to_path_handle(reference)?;
+
+    // the haplotypes of each sample, with their paths
+    let mut haplotypes: Vec<(BString, BString, Vec<&G::PathHandle>)> = vec![];
+    let mut paths: Vec<(&[u8], &G::PathHandle)> = graph
+        .paths_iter()
+        .filter(|&p| *p != ref_path)
+        .map(|p| (graph.path_handle_to_name(p), p))
+        .collect();
+    paths.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, path) in paths {
+        let sample = pansn_sample(name);
+        let haplotype = pansn_haplotype(name);
+        match haplotypes
+            .iter_mut()
+            .find(|(s, h, _)| s == sample && h == haplotype)
+        {
+            Some((_, _, paths)) => paths.push(path),
+            None => haplotypes.push((sample.into(), haplotype.into(), vec![path])),
+        }
+    }
+    haplotypes.sort_by(|a, b| {
+        let number = |h: &BString| h.to_str().ok().and_then(|h| h.parse::<usize>().ok());
+        (&a.0, number(&a.1), &a.1).cmp(&(&b.0, number(&b.1), &b.1))
+    });
+
+    let mut samples: Vec<BString> = haplotypes.iter().map(|(s, _, _)| s.clone()).collect();
+    samples.dedup();
+
+    let bubbles = find_bubbles(graph, MAX_BUBBLE_SIZE);
+    let on_reference = |node: NodeId| {
+        graph
+            .occurrences_iter(Handle::pack(node, false))
+            .any(|step| graph.path_handle_of_step(&step) == ref_path)
+    };
+    let anchored: Vec<bool> = bubbles
+        .iter()
+        .map(|b| on_reference(b.start.id()) && on_reference(b.end.id()))
+        .collect();
+
+    let sequence_of = |walk: &[Handle]| -> BString {
+        walk.iter()
+            .flat_map(|&h| graph.sequence(h))
+            .collect::<Vec<u8>>()
+            .into()
+    };
+
+    let mut records = vec![];
+    for (ix, bubble) in bubbles.iter().enumerate() {
+        // only the outermost bubbles on the reference
+        let mut parent = bubble.parent;
+        let mut nested = false;
+        while let Some(p) = parent {
+            nested |= anchored[p];
+            parent = bubbles[p].parent;
+        }
+        if !anchored[ix] || nested {
+            continue;
+        }
+
+        // a bubble the reference can't be followed through is skipped
+        let (mut left, mut right) = (bubble.start.id(), bubble.end.id());
+        let (mut start, right_pos, mut left_handle, mut ref_walk) =
+            match walk_between(graph, &ref_path, left, right) {
+                Some(walk) => walk,
+                None => continue,
+            };
+        if start > right_pos {
+            std::mem::swap(&mut left, &mut right);
+            match walk_between(graph, &ref_path, left, right) {
+                Some((pos, _, handle, walk)) => {
+                    start = pos;
+                    left_handle = handle;
+                    ref_walk = walk;
+                }
+                None => continue,
+            }
+        }
+        let start = start + graph.node_len(left_handle);
+
+        let mut alleles: Vec<BString> = vec![sequence_of(&ref_walk)];
+        let mut calls: Vec<Option<usize>> = vec![];
+        for (_, _, paths) in haplotypes.iter() {
+            let walk = paths
+                .iter()
+                .find_map(|path| walk_between(graph, path, left, right).map(|(_, _, _, w)| w));
+            let call = walk.map(|walk| {
+                let allele = sequence_of(&walk);
+                alleles
+                    .iter()
+                    .position(|a| *a == allele)
+                    .unwrap_or_else(|| {
+                        alleles.push(allele);
+                        alleles.len() - 1
+                    })
+            });
+            calls.push(call);
+        }
+        if alleles.len() == 1 {
+            continue;
+        }
+
+        let mut pos = start + 1;
+        if alleles.iter().any(|a| a.is_empty()) {
+            let anchor = match graph.sequence(left_handle).last() {
+                Some(&base) => base,
+                None => continue,
+            };
+            for allele in alleles.iter_mut() {
+                allele.insert(0, anchor);
+            }
+            pos -= 1;
+        }
+
+        let mut genotypes: Vec<Genotype> = vec![];
+        for (i, (sample, _, _)) in haplotypes.iter().enumerate() {
+            let sample_ix = samples.iter().position(|s| s == sample).unwrap();
+            if genotypes.len() <= sample_ix {
+                genotypes.push(Genotype {
+                    alleles: vec![],
+                    phased: true,
+                });
+            }
+            genotypes[sample_ix].alleles.push(calls[i]);
+        }
+
+        let boundary = |h: Handle| format!("{}{}", if h.is_reverse() { '<' } else { '>' }, h.id());
+        records.push(VcfRecord {
+            chrom: reference.into(),
+            pos,
+            id: format!("{}{}", boundary(bubble.start), boundary(bubble.end)).into(),
+            reference: alleles[0].clone(),
+            alternatives: alleles[1..].to_vec(),
+            info: BString::from("."),
+            genotypes,
+        });
+    }
+    records.sort_by_key(|r| r.pos);
+
+    Some(Vcf { samples, records })
+}
+
+fn format_genotype(genotype: &Genotype) -> String {
+    let separator = if genotype.phased { "|" } else { "/" };
+    genotype
+        .alleles
+        .iter()
+        .map(|a| a.map_or_else(|| ".".to_string(), |a| a.to_string()))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Function that writes a VCF file, with a header declaring the contigs
+/// of the records and the GT field; QUAL and FILTER are left empty
+/// # Example
+/// ```ignore
+/// let file = std::fs::File::create("./variants.vcf")?;
+/// write_vcf(std::io::BufWriter::new(file), &vcf)?;
+/// ```
+pub fn write_vcf<W: Write>(mut writer: W, vcf: &Vcf) -> io::Result<()> {
+    writeln!(writer, "##fileformat=VCFv4.2")?;
+    let mut contigs: Vec<&BString> = vec![];
+    for record in vcf.records.iter() {
+        if !contigs.contains(&&record.chrom) {
+            contigs.push(&record.chrom);
+        }
+    }
+    for contig in contigs {
+        writeln!(writer, "##contig=<ID={}>", contig)?;
+    }
+    writeln!(
+        writer,
+        "##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">"
+    )?;
+
+    write!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+    if !vcf.samples.is_empty() {
+        write!(writer, "\tFORMAT")?;
+        for sample in vcf.samples.iter() {
+            write!(writer, "\t{}", sample)?;
+        }
+    }
+    writeln!(writer)?;
+
+    for record in vcf.records.iter() {
+        let alternatives = if record.alternatives.is_empty() {
+            BString::from(".")
+        } else {
+            bstr::join(",", record.alternatives.iter()).into()
+        };
+        write!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t.\t.\t{}",
+            record.chrom, record.pos, record.id, record.reference, alternatives, record.info
+        )?;
+        if !vcf.samples.is_empty() {
+            write!(writer, "\tGT")?;
+            for s in 0..vcf.samples.len() {
+                match record.genotypes.get(s) {
+                    Some(genotype) => write!(writer, "\t{}", format_genotype(genotype))?,
+                    None => write!(writer, "\t.")?,
+                }
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1031 @@
+use handlegraph2::{
+    algorithms::*,
+    handle::{Edge, Handle, NodeId},
+    handlegraph::*,
+    hashgraph::HashGraph,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+/*
+edges
+1  -> 2 -> 4
+  \-> 3 -/
+ */
+fn diamond_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    let h1 = graph.create_handle(b"ACGT", 1);
+    let h2 = graph.create_handle(b"GG", 2);
+    let h3 = graph.create_handle(b"TTTTT", 3);
+    let h4 = graph.create_handle(b"CA", 4);
+
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h1, h3));
+    graph.create_edge(Edge(h2, h4));
+    graph.create_edge(Edge(h3, h4));
+
+    graph
+}
+
+#[test]
+fn shortest_path_takes_shorter_branch() {
+    let graph = diamond_graph();
+    let h1 = Handle::pack(1, false);
+    let h2 = Handle::pack(2, false);
+    let h4 = Handle::pack(4, false);
+
+    let path = shortest_path(&graph, (h1, 1), (h4, 1), 100).unwrap();
+    assert_eq!(path.distance, 6);
+    assert_eq!(path.walk, vec![h1, h2, h4]);
+
+    // same handle, target further along
+    let path = shortest_path(&graph, (h1, 1), (h1, 3), 100).unwrap();
+    assert_eq!(path.distance, 2);
+    assert_eq!(path.walk, vec![h1]);
+
+    // too far away
+    assert_eq!(shortest_path(&graph, (h1, 1), (h4, 1), 5), None);
+}
+
+#[test]
+fn shortest_path_respects_orientation() {
+    let graph = diamond_graph();
+    let h1 = Handle::pack(1, false);
+    let h3 = Handle::pack(3, false);
+    let h4 = Handle::pack(4, false);
+
+    // there is no walk going backwards on the forward strand
+    assert_eq!(shortest_path(&graph, (h4, 0), (h1, 0), 100), None);
+
+    // but there is one on the reverse strand
+    let path = shortest_path(&graph, (h4.flip(), 0), (h1.flip(), 0), 100).unwrap();
+    assert_eq!(path.distance, 4);
+    assert_eq!(path.walk.len(), 3);
+    assert_eq!(path.walk[0], h4.flip());
+    assert_eq!(path.walk[2], h1.flip());
+
+    let path = shortest_path(&graph, (h3.flip(), 4), (h1.flip(), 3), 100).unwrap();
+    assert_eq!(path.distance, 4);
+    assert_eq!(path.walk, vec![h3.flip(), h1.flip()]);
+}
+
+/*
+edges
+1 -> 2 -> 3 -> 5 -> 7
+  \    \-> 4 -/    /
+   \-> 6 ---------/
+ */
+fn nested_bubbles_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    let handles: Vec<Handle> = (1..=7u64)
+        .map(|id| graph.create_handle(b"ACG", id))
+        .collect();
+    let edge = |l: usize, r: usize| Edge(handles[l - 1], handles[r - 1]);
+
+    for &(l, r) in [
+        (1, 2),
+        (2, 3),
+        (2, 4),
+        (3, 5),
+        (4, 5),
+        (5, 7),
+        (1, 6),
+        (6, 7),
+    ]
+    .iter()
+    {
+        graph.create_edge(edge(l, r));
+    }
+
+    graph
+}
+
+#[test]
+fn find_nested_bubbles() {
+    use handlegraph2::handle::NodeId;
+
+    let graph = nested_bubbles_graph();
+    let bubbles = find_bubbles(&graph, MAX_BUBBLE_SIZE);
+
+    assert_eq!(bubbles.len(), 2);
+
+    let outer = &bubbles[0];
+    assert_eq!(outer.start, Handle::pack(1, false));
+    assert_eq!(outer.end, Handle::pack(7, false));
+    let contained: Vec<NodeId> = vec![2, 3, 4, 5, 6].into_iter().map(NodeId::from).collect();
+    assert_eq!(outer.contained, contained);
+    assert_eq!(outer.parent, None);
+    assert_eq!(outer.children, vec![1]);
+
+    let inner = &bubbles[1];
+    assert_eq!(inner.start, Handle::pack(2, false));
+    assert_eq!(inner.end, Handle::pack(5, false));
+    let contained: Vec<NodeId> = vec![3, 4].into_iter().map(NodeId::from).collect();
+    assert_eq!(inner.contained, contained);
+    assert_eq!(inner.parent, Some(0));
+    assert!(inner.children.is_empty());
+
+    // the outer bubble has 5 nodes inside
+    let bounded = find_bubbles(&graph, 4);
+    assert_eq!(bounded.len(), 1);
+    assert_eq!(bounded[0].start, Handle::pack(2, false));
+    assert_eq!(bounded[0].parent, None);
+}
+
+#[test]
+fn no_bubbles_across_tips_or_cycles() {
+    let mut graph = nested_bubbles_graph();
+    // a cycle from the exit back to the entrance of the outer bubble
+    graph.create_edge(Edge(Handle::pack(7, false), Handle::pack(1, false)));
+
+    let bubbles = find_bubbles(&graph, MAX_BUBBLE_SIZE);
+    assert_eq!(bubbles.len(), 1);
+    assert_eq!(bubbles[0].start, Handle::pack(2, false));
+    assert_eq!(bubbles[0].parent, None);
+
+    // a tip inside the inner bubble breaks it too
+    let tip = graph.create_handle(b"T", 8);
+    graph.create_edge(Edge(Handle::pack(3, false), tip));
+
+    assert!(find_bubbles(&graph, MAX_BUBBLE_SIZE).is_empty());
+}
+
+/*
+paths
+a: 1+ 2+ 4+
+b: 1+ 3+ 4+
+c: 4- 2- 1-
+ */
+fn liftover_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    let h1 = graph.create_handle(b"AC", 1);
+    let h2 = graph.create_handle(b"GT", 2);
+    let h3 = graph.create_handle(b"TTT", 3);
+    let h4 = graph.create_handle(b"CA", 4);
+
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h1, h3));
+    graph.create_edge(Edge(h2, h4));
+    graph.create_edge(Edge(h3, h4));
+
+    let a = graph.create_path_handle(b"a", false);
+    let b = graph.create_path_handle(b"b", false);
+    let c = graph.create_path_handle(b"c", false);
+    for &h in [h1, h2, h4].iter() {
+        graph.append_step(&a, h);
+    }
+    for &h in [h1, h3, h4].iter() {
+        graph.append_step(&b, h);
+    }
+    for &h in [h4, h2, h1].iter() {
+        graph.append_step(&c, h.flip());
+    }
+
+    graph
+}
+
+#[test]
+fn liftover_positions() {
+    let graph = liftover_graph();
+    let a = graph.name_to_path_handle(b"a").unwrap();
+    let b = graph.name_to_path_handle(b"b").unwrap();
+    let c = graph.name_to_path_handle(b"c").unwrap();
+
+    let lifted = lift_position(&graph, &a, 0, &b).unwrap();
+    assert_eq!(lifted.position, 0);
+    assert!(!lifted.is_reverse);
+
+    assert_eq!(lift_position(&graph, &a, 5, &b).unwrap().position, 6);
+    // node 2 is not on b
+    assert_eq!(lift_position(&graph, &a, 2, &b), None);
+    // past the end of a
+    assert_eq!(lift_position(&graph, &a, 6, &b), None);
+
+    let lifted = lift_position(&graph, &a, 0, &c).unwrap();
+    assert_eq!(lifted.position, 5);
+    assert!(lifted.is_reverse);
+}
+
+#[test]
+fn liftover_intervals() {
+    use LiftedInterval::*;
+
+    let graph = liftover_graph();
+    let a = graph.name_to_path_handle(b"a").unwrap();
+    let b = graph.name_to_path_handle(b"b").unwrap();
+    let c = graph.name_to_path_handle(b"c").unwrap();
+
+    assert_eq!(
+        lift_interval(&graph, &a, 1..8, &b),
+        vec![
+            Mapped {
+                from: 1..2,
+                to: 1..2,
+                is_reverse: false
+            },
+            Unmapped { from: 2..4 },
+            Mapped {
+                from: 4..6,
+                to: 5..7,
+                is_reverse: false
+            },
+            Unmapped { from: 6..8 },
+        ]
+    );
+
+    assert_eq!(
+        lift_interval(&graph, &a, 0..6, &c),
+        vec![Mapped {
+            from: 0..6,
+            to: 0..6,
+            is_reverse: true
+        }]
+    );
+}
+
+/*
+paths
+HG1#1#chr1: 1+ 2+ 4+ 2+
+HG1#2#chr1: 1+ 3+ 4+
+HG2#1#chr1: 1+ 3+ 4+
+node 5 is on no path
+ */
+fn coverage_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    let h1 = graph.create_handle(b"AC", 1);
+    let h2 = graph.create_handle(b"GT", 2);
+    let h3 = graph.create_handle(b"TTT", 3);
+    let h4 = graph.create_handle(b"CA", 4);
+    graph.create_handle(b"GATTACA", 5);
+
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h1, h3));
+    graph.create_edge(Edge(h2, h4));
+    graph.create_edge(Edge(h3, h4));
+    graph.create_edge(Edge(h4, h2));
+
+    let paths: [(&[u8], Vec<Handle>); 3] = [
+        (b"HG1#1#chr1", vec![h1, h2, h4, h2]),
+        (b"HG1#2#chr1", vec![h1, h3, h4]),
+        (b"HG2#1#chr1", vec![h1, h3, h4]),
+    ];
+    for (name, handles) in paths.iter() {
+        let p = graph.create_path_handle(name, false);
+        for &handle in handles.iter() {
+            graph.append_step(&p, handle);
+        }
+    }
+
+    graph
+}
+
+#[test]
+fn node_and_edge_depth() {
+    let graph = coverage_graph();
+    let coverage = path_coverage(&graph);
+    let h = |id: u64| Handle::pack(id, false);
+
+    assert_eq!(
+        coverage.node_depth(1),
+        Depth {
+            paths: 3,
+            visits: 3
+        }
+    );
+    assert_eq!(
+        coverage.node_depth(2),
+        Depth {
+            paths: 1,
+            visits: 2
+        }
+    );
+    assert_eq!(
+        coverage.node_depth(3),
+        Depth {
+            paths: 2,
+            visits: 2
+        }
+    );
+    assert_eq!(
+        coverage.node_depth(5),
+        Depth {
+            paths: 0,
+            visits: 0
+        }
+    );
+
+    assert_eq!(
+        coverage.edge_depth(h(1), h(3)),
+        Depth {
+            paths: 2,
+            visits: 2
+        }
+    );
+    assert_eq!(
+        coverage.edge_depth(h(2), h(4)),
+        Depth {
+            paths: 1,
+            visits: 1
+        }
+    );
+    assert_eq!(
+        coverage.edge_depth(h(4), h(2)),
+        Depth {
+            paths: 1,
+            visits: 1
+        }
+    );
+    // the same edge seen from the other strand
+    assert_eq!(
+        coverage.edge_depth(h(2).flip(), h(4).flip()),
+        Depth {
+            paths: 1,
+            visits: 1
+        }
+    );
+}
+
+#[test]
+fn presence_and_summary() {
+    use bstr::BString;
+
+    let graph = coverage_graph();
+
+    let matrix = presence_matrix(&graph, |name| pansn_sample(name).into());
+    assert_eq!(
+        matrix.groups,
+        vec![BString::from("HG1"), BString::from("HG2")]
+    );
+    assert_eq!(
+        matrix.presence,
+        vec![
+            vec![true, true],
+            vec![true, false],
+            vec![true, true],
+            vec![true, true],
+            vec![false, false],
+        ]
+    );
+
+    let summary = coverage_summary(&graph, &matrix);
+    assert_eq!(summary.core_nodes, 3);
+    assert_eq!(summary.core_bases, 7);
+    assert_eq!(summary.cloud_nodes, 1);
+    assert_eq!(summary.cloud_bases, 2);
+    assert_eq!(summary.shell_nodes, 0);
+    assert_eq!(summary.uncovered_nodes, 1);
+    assert_eq!(summary.uncovered_bases, 7);
+
+    // one group per haplotype
+    let matrix = presence_matrix(&graph, |name| name.into());
+    let summary = coverage_summary(&graph, &matrix);
+    assert_eq!(summary.core_nodes, 2);
+    assert_eq!(summary.shell_nodes, 1);
+    assert_eq!(summary.cloud_nodes, 1);
+}
+
+#[test]
+fn kmers_across_nodes_and_strands() {
+    // 1: ACGT, 2: GG, 3: TTTTT, 4: CA
+    let graph = diamond_graph();
+    let h = |id: u64| Handle::pack(id, false);
+
+    let index = kmer_index(&graph, 3, 16);
+    assert_eq!(index.skipped, 0);
+
+    // ACGT is its own reverse complement
+    assert_eq!(index.positions(b"ACG"), &[(h(1), 0), (h(1).flip(), 0)]);
+    // crossing 1+ -> 2+ and 1+ -> 3+
+    assert_eq!(index.positions(b"GTG"), &[(h(1), 2)]);
+    assert_eq!(index.positions(b"GTT"), &[(h(1), 2)]);
+    // crossing three nodes
+    assert_eq!(index.positions(b"TGG"), &[(h(1), 3)]);
+    assert_eq!(index.positions(b"GCA"), &[(h(2), 1)]);
+    // the reverse strand, CA- is TG
+    assert_eq!(index.positions(b"TGA"), &[(h(4).flip(), 0)]);
+    assert_eq!(index.positions(b"GCC"), &[(h(4).flip(), 1)]);
+    assert_eq!(index.positions(b"CCC"), &[]);
+
+    // k-mers ending in a tip shorter than k - 1
+    let mut tipped = diamond_graph();
+    let tip = tipped.create_handle(b"G", 5);
+    tipped.create_edge(Edge(h(4), tip));
+    let index = kmer_index(&tipped, 3, 16);
+    assert_eq!(index.positions(b"CAG"), &[(h(4), 0)]);
+    assert_eq!(index.positions(b"CTG"), &[(tip.flip(), 0)]);
+
+    // only the walks through 2 are on the path
+    let mut graph = graph;
+    let p = graph.create_path_handle(b"path", false);
+    for &handle in [h(1), h(2), h(4)].iter() {
+        graph.append_step(&p, handle);
+    }
+    let index = path_kmer_index(&graph, 3);
+    assert_eq!(index.positions(b"GTG"), &[(h(1), 2)]);
+    assert_eq!(index.positions(b"GTT"), &[]);
+    assert_eq!(index.positions(b"CCA"), &[(h(2).flip(), 0)]);
+    assert_eq!(index.len(), 10);
+}
+
+#[test]
+fn kmer_index_caps_dense_regions() {
+    let graph = diamond_graph();
+    let h1 = Handle::pack(1, false);
+
+    // two walks of 3 bases leave 1+, and two leave 4-
+    let index = kmer_index(&graph, 4, 1);
+    assert_eq!(index.skipped, 3 + 2);
+    assert_eq!(index.positions(b"ACGT"), &[(h1, 0), (h1.flip(), 0)]);
+    assert_eq!(index.positions(b"CGTG"), &[]);
+
+    let index = kmer_index(&graph, 4, 2);
+    assert_eq!(index.positions(b"CGTG"), &[(h1, 1)]);
+}
+
+#[test]
+fn find_sequence_across_nodes() {
+    // 1: ACGT, 2: GG, 3: TTTTT, 4: CA
+    let graph = diamond_graph();
+    let h = |id: u64| Handle::pack(id, false);
+
+    assert_eq!(
+        find_sequence(&graph, b"GTGGC"),
+        vec![SequenceMatch {
+            walk: vec![h(1), h(2), h(4)],
+            start: 2,
+            end: 1,
+        }]
+    );
+
+    // case insensitive, inside a single node or not
+    let matches: Vec<_> = find_sequence(&graph, b"tttt")
+        .into_iter()
+        .map(|m| (m.walk, m.start, m.end))
+        .collect();
+    assert_eq!(
+        matches,
+        vec![
+            (vec![h(1), h(3)], 3, 3),
+            (vec![h(3)], 0, 4),
+            (vec![h(3)], 1, 5),
+        ]
+    );
+
+    // on the reverse strand: CA- GG- ACGT- is TG CC ACGT
+    assert_eq!(
+        find_sequence(&graph, b"GCCA"),
+        vec![SequenceMatch {
+            walk: vec![h(4).flip(), h(2).flip(), h(1).flip()],
+            start: 1,
+            end: 1,
+        }]
+    );
+
+    assert!(find_sequence(&graph, b"GGGG").is_empty());
+    assert!(find_sequence(&graph, b"").is_empty());
+}
+
+#[test]
+fn topological_order_and_cycles() {
+    let mut graph = diamond_graph();
+    let order: Vec<u64> = topological_sort(&graph)
+        .unwrap()
+        .iter()
+        .map(|h| h.id().into())
+        .collect();
+    assert_eq!(order, vec![1, 2, 3, 4]);
+
+    graph.create_edge(Edge(Handle::pack(4, false), Handle::pack(1, false)));
+    assert!(topological_sort(&graph).is_none());
+}
+
+#[test]
+fn align_global_and_local() {
+    let graph = diamond_graph();
+    let handles: Vec<Handle> = (1..=4).map(|id| Handle::pack(id, false)).collect();
+    let strings =
+        |a: &Alignment| -> Vec<String> { a.cigars.iter().map(|c| cigar_string(c)).collect() };
+
+    let scoring = Scoring::default();
+    let exact = align_sequence(&graph, b"ACGTGGCA", &scoring, AlignmentMode::Global).unwrap();
+    assert_eq!(exact.score, 8);
+    assert_eq!(exact.walk, vec![handles[0], handles[1], handles[3]]);
+    assert_eq!(strings(&exact), vec!["4=", "2=", "2="]);
+
+    let gapped = align_sequence(&graph, b"ACGTCA", &scoring, AlignmentMode::Global).unwrap();
+    assert_eq!(gapped.score, -1);
+    assert_eq!(gapped.walk, vec![handles[0], handles[1], handles[3]]);
+    assert_eq!(strings(&gapped), vec!["4=", "2D", "2="]);
+    assert_eq!((gapped.start, gapped.end), (0, 2));
+
+    let local = align_sequence(&graph, b"AAAATTTTTCAAAA", &scoring, AlignmentMode::Local).unwrap();
+    assert_eq!(local.score, 7);
+    assert_eq!((local.query_start, local.query_end), (4, 11));
+    assert_eq!(local.walk, vec![handles[2], handles[3]]);
+    assert_eq!(strings(&local), vec!["5=", "2="]);
+
+    let mismatch = align_sequence(
+        &graph,
+        b"CGTTTATT",
+        &Scoring {
+            mismatch: 1,
+            ..scoring
+        },
+        AlignmentMode::Local,
+    )
+    .unwrap();
+    assert_eq!(mismatch.score, 6);
+    assert_eq!(mismatch.walk, vec![handles[0], handles[2]]);
+    assert_eq!(strings(&mismatch), vec!["3=", "2=1X2="]);
+    assert_eq!((mismatch.start, mismatch.end), (1, 5));
+}
+
+#[test]
+fn merge_graphs_by_offset_and_squeeze() {
+    let mut first = diamond_graph();
+    let p = first.create_path_handle(b"x", false);
+    first.append_step(&p, Handle::pack(1, false));
+
+    let mut second = HashGraph::new();
+    let h1 = second.create_handle(b"ACGT", 1);
+    let h2 = second.create_handle(b"CC", 2);
+    let h5 = second.create_handle(b"T", 5);
+    second.create_edge(Edge(h1, h2));
+    second.create_edge(Edge(h2, h5.flip()));
+    let p = second.create_path_handle(b"x", false);
+    for &h in [h1, h2, h5.flip()].iter() {
+        second.append_step(&p, h);
+    }
+
+    let sorted_map = |map: fnv::FnvHashMap<NodeId, NodeId>| {
+        let mut map: Vec<(u64, u64)> = map.into_iter().map(|(a, b)| (a.into(), b.into())).collect();
+        map.sort();
+        map
+    };
+
+    let mut offset = first.clone();
+    let map = merge_graphs(&mut offset, &second, MergeIds::Offset);
+    assert_eq!(sorted_map(map), vec![(1, 5), (2, 6), (5, 9)]);
+    assert_eq!(offset.node_count(), 7);
+    assert_eq!(offset.edge_count(), 6);
+
+    let mut squeezed = first.clone();
+    let map = merge_graphs(&mut squeezed, &second, MergeIds::Squeeze);
+    assert_eq!(sorted_map(map), vec![(1, 1), (2, 6), (5, 9)]);
+    assert_eq!(squeezed.node_count(), 6);
+    assert_eq!(squeezed.edge_count(), 6);
+    assert!(squeezed.has_edge(Handle::pack(6, false), Handle::pack(9, true)));
+
+    let renamed = squeezed.name_to_path_handle(b"x.1").unwrap();
+    assert_eq!(
+        squeezed.paths[&renamed].nodes,
+        vec![
+            Handle::pack(1, false),
+            Handle::pack(6, false),
+            Handle::pack(9, true)
+        ]
+    );
+    assert!(squeezed.validate().is_empty());
+
+    // a source node with id 0 doesn't collide with the largest target id
+    let mut zero = HashGraph::new();
+    zero.create_handle(b"A", 0);
+    zero.create_handle(b"C", 3);
+    let map = merge_graphs(&mut squeezed, &zero, MergeIds::Offset);
+    assert_eq!(sorted_map(map), vec![(0, 10), (3, 13)]);
+    assert_eq!(squeezed.node_count(), 8);
+
+    // the edge and the step left behind by a removed node are dropped
+    let mut dangling = second.clone();
+    dangling.graph.remove(&h5.id());
+    let mut target = HashGraph::new();
+    let map = merge_graphs(&mut target, &dangling, MergeIds::Offset);
+    assert_eq!(sorted_map(map), vec![(1, 1), (2, 2)]);
+    assert_eq!(target.edge_count(), 1);
+    let x = target.name_to_path_handle(b"x").unwrap();
+    assert_eq!(target.paths[&x].nodes, vec![h1, h2]);
+    assert!(target.validate().is_empty());
+}
+
+#[test]
+fn diff_two_graph_versions() {
+    let mut old = diamond_graph();
+    let p = old.create_path_handle(b"x", false);
+    for &id in [1, 2, 4].iter() {
+        old.append_step(&p, Handle::pack(id, false));
+    }
+    let p = old.create_path_handle(b"y", false);
+    old.append_step(&p, Handle::pack(3, false));
+
+    assert!(graph_diff(&old, &old.clone()).is_empty());
+
+    let mut new = old.clone();
+    new.modify_handle(2, b"GC");
+    new.remove_path(b"y");
+    let h5 = new.create_handle(b"T", 5);
+    // the same edge as 4+ -> 5+, stored from the other side
+    new.create_edge(Edge(h5.flip(), Handle::pack(4, true)));
+    new.remove_edge(Edge(Handle::pack(1, false), Handle::pack(3, false)));
+
+    let diff = graph_diff(&old, &new);
+    assert_eq!(diff.added_nodes, vec![(NodeId::from(5), "T".into())]);
+    assert!(diff.removed_nodes.is_empty());
+    assert_eq!(
+        diff.changed_nodes,
+        vec![(NodeId::from(2), "GG".into(), "GC".into())]
+    );
+    assert_eq!(
+        diff.added_edges,
+        vec![Edge(Handle::pack(4, false), Handle::pack(5, false))]
+    );
+    assert_eq!(
+        diff.removed_edges,
+        vec![Edge(Handle::pack(1, false), Handle::pack(3, false))]
+    );
+    assert!(diff.added_paths.is_empty());
+    assert_eq!(diff.removed_paths, vec![bstr::BString::from("y")]);
+    assert_eq!(
+        diff.changed_paths,
+        vec![PathDiff {
+            name: "x".into(),
+            steps_differ: false,
+            sequence_differs: true,
+        }]
+    );
+
+    let mut out = vec![];
+    write_graph_diff(&mut out, &diff).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "node\t+\t5\tT\nnode\t~\t2\tGG\tGC\nedge\t+\t4+\t5+\nedge\t-\t1+\t3+\n\
+         path\t-\ty\npath\t~\tx\tsequence\n"
+    );
+}
+
+#[test]
+fn equivalence_modulo_splitting_and_ids() {
+    let with_paths = |mut graph: HashGraph, x: &[Handle], y: &[Handle]| {
+        for (name, walk) in [(&b"x"[..], x), (b"y", y)].iter() {
+            let p = graph.create_path_handle(name, false);
+            for &h in walk.iter() {
+                graph.append_step(&p, h);
+            }
+        }
+        graph
+    };
+    let h = |id: u64| Handle::pack(id, false);
+    let graph = with_paths(diamond_graph(), &[h(1), h(2), h(4)], &[h(1), h(3), h(4)]);
+
+    // split and renumbered
+    let mut chopped = graph.clone();
+    chopped.divide_handle(h(3), vec![2]);
+    chopped.divide_handle(h(1), vec![1, 3]);
+    chopped.compact_ids(Some(&[NodeId::from(4), NodeId::from(2)]));
+    assert_eq!(check_equivalence(&graph, &chopped), Ok(()));
+    assert!(equivalent(&chopped, &graph));
+
+    // the GG node stored on the other strand, as CC
+    let mut flipped = HashGraph::new();
+    let f1 = flipped.create_handle(b"AC", 10);
+    let f2 = flipped.create_handle(b"GT", 11);
+    let f3 = flipped.create_handle(b"CC", 12);
+    let f4 = flipped.create_handle(b"TTTTT", 13);
+    let f5 = flipped.create_handle(b"CA", 14);
+    flipped.create_edge(Edge(f1, f2));
+    flipped.create_edge(Edge(f2, f3.flip()));
+    flipped.create_edge(Edge(f3.flip(), f5));
+    flipped.create_edge(Edge(f2, f4));
+    flipped.create_edge(Edge(f4, f5));
+    let flipped = with_paths(flipped, &[f1, f2, f3.flip(), f5], &[f1, f2, f4, f5]);
+    assert_eq!(check_equivalence(&graph, &flipped), Ok(()));
+
+    let mut changed = graph.clone();
+    changed.modify_handle(3, b"TTATT");
+    assert_eq!(
+        check_equivalence(&graph, &changed),
+        Err(EquivalenceError::PathSequence("y".into()))
+    );
+
+    let mut missing = graph.clone();
+    missing.remove_path(b"y");
+    assert_eq!(
+        check_equivalence(&graph, &missing),
+        Err(EquivalenceError::PathMissing("y".into()))
+    );
+
+    // same sequences, but one more edge
+    let mut extra = graph.clone();
+    extra.create_edge(Edge(h(2), h(3)));
+    assert_eq!(
+        check_equivalence(&graph, &extra),
+        Err(EquivalenceError::Structure)
+    );
+}
+
+#[test]
+fn equivalence_of_path_routes() {
+    // two identical branches: 1 -> 2 -> 4, 1 -> 3 -> 4
+    let branches = |routes: &[&[u64]]| {
+        let mut graph = HashGraph::new();
+        for (id, seq) in [(1, "A"), (2, "CC"), (3, "CC"), (4, "G")].iter() {
+            graph.create_handle(seq.as_bytes(), *id as u64);
+        }
+        for &(l, r) in [(1, 2), (1, 3), (2, 4), (3, 4)].iter() {
+            graph.create_edge(Edge(Handle::pack(l, false), Handle::pack(r, false)));
+        }
+        for (i, route) in routes.iter().enumerate() {
+            let p = graph.create_path_handle(format!("p{}", i).as_bytes(), false);
+            for &id in route.iter() {
+                graph.append_step(&p, Handle::pack(id, false));
+            }
+        }
+        graph
+    };
+
+    let one = branches(&[&[1, 2, 4]]);
+    let other = branches(&[&[1, 3, 4]]);
+    assert_eq!(check_equivalence(&one, &other), Ok(()));
+
+    let apart = branches(&[&[1, 2, 4], &[1, 3, 4]]);
+    let together = branches(&[&[1, 2, 4], &[1, 2, 4]]);
+    assert_eq!(
+        check_equivalence(&apart, &together),
+        Err(EquivalenceError::PathRoute("p1".into()))
+    );
+}
+
+#[test]
+fn clip_tips_and_prune_coverage() {
+    let h = |id: u64| Handle::pack(id, false);
+    let mut graph = diamond_graph();
+    let tip = graph.create_handle(b"A", 5);
+    let long_tip = graph.create_handle(b"AAAAAAAAAA", 6);
+    graph.create_handle(b"C", 7);
+    graph.create_edge(Edge(h(2), tip));
+    graph.create_edge(Edge(long_tip.flip(), h(1).flip()));
+    for (name, walk) in [("x", [1, 2, 4]), ("y", [1, 3, 4]), ("z", [1, 2, 4])].iter() {
+        let p = graph.create_path_handle(name.as_bytes(), false);
+        for &id in walk.iter() {
+            graph.append_step(&p, h(id));
+        }
+    }
+
+    // the ends of the paths are tips too, but they are kept
+    let report = clip_tips(&mut graph, 5, true);
+    assert_eq!(report.nodes, vec![NodeId::from(5)]);
+    assert_eq!(report.edges, vec![Edge(h(2), h(5))]);
+    assert!(report.paths.is_empty());
+    assert!(graph.has_node(5));
+    assert_eq!(clip_tips(&mut graph, 5, false), report);
+    assert!(!graph.has_node(5));
+    assert!(clip_tips(&mut graph, 5, false).is_empty());
+
+    let report = remove_isolated(&mut graph, false);
+    assert_eq!(report.nodes, vec![NodeId::from(7)]);
+    assert!(!graph.has_node(7));
+
+    let report = prune_low_coverage(&mut graph, 2, true);
+    assert_eq!(report.nodes, vec![NodeId::from(3), NodeId::from(6)]);
+    assert_eq!(
+        report.edges,
+        vec![Edge(h(1), h(3)), Edge(h(1), h(6)), Edge(h(3), h(4))]
+    );
+    assert_eq!(report.paths, vec![bstr::BString::from("y")]);
+    assert_eq!(graph.node_count(), 5);
+
+    prune_low_coverage(&mut graph, 2, false);
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 2);
+    assert!(!graph.has_path(b"y"));
+    assert!(graph.has_path(b"x"));
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn prune_dense_regions() {
+    /*
+    1 -> 2 -> 4 -> 5 -> 7
+      \> 3 -/  \> 6 -/
+     */
+    let h = |id: u64| Handle::pack(id, false);
+    let mut graph = HashGraph::new();
+    for (id, seq) in [
+        (1, "AAAA"),
+        (2, "C"),
+        (3, "G"),
+        (4, "T"),
+        (5, "C"),
+        (6, "G"),
+        (7, "AAAA"),
+    ]
+    .iter()
+    {
+        graph.create_handle(seq.as_bytes(), *id as u64);
+    }
+    for &(l, r) in [
+        (1, 2),
+        (1, 3),
+        (2, 4),
+        (3, 4),
+        (4, 5),
+        (4, 6),
+        (5, 7),
+        (6, 7),
+    ]
+    .iter()
+    {
+        graph.create_edge(Edge(h(l), h(r)));
+    }
+    let p = graph.create_path_handle(b"ref", false);
+    for &id in [1, 2, 4, 5, 7].iter() {
+        graph.append_step(&p, h(id));
+    }
+
+    // every 4-mer crosses at most 3 edges
+    let (pruned, report) = prune_complex_regions(&graph, 4, 3, MAX_WALKS, true);
+    assert!(report.removed.is_empty() && report.restored.is_empty());
+    assert_eq!(pruned.edge_count(), 8);
+
+    let (pruned, report) = prune_complex_regions(&graph, 4, 2, MAX_WALKS, true);
+    assert_eq!(
+        report.removed,
+        vec![
+            Edge(h(1), h(3)),
+            Edge(h(3), h(4)),
+            Edge(h(4), h(6)),
+            Edge(h(6), h(7))
+        ]
+    );
+    assert_eq!(
+        report.restored,
+        vec![
+            Edge(h(1), h(2)),
+            Edge(h(2), h(4)),
+            Edge(h(4), h(5)),
+            Edge(h(5), h(7))
+        ]
+    );
+    assert!(report.paths.is_empty());
+    assert_eq!(pruned.edge_count(), 4);
+    assert_eq!(pruned.node_count(), 7);
+    assert!(pruned.validate().is_empty());
+
+    let (pruned, report) = prune_complex_regions(&graph, 4, 2, MAX_WALKS, false);
+    assert_eq!(report.removed.len(), 8);
+    assert_eq!(report.paths, vec![bstr::BString::from("ref")]);
+    assert_eq!(pruned.edge_count(), 0);
+    assert_eq!(pruned.path_count(), 0);
+    assert!(pruned.validate().is_empty());
+    // the original graph is untouched
+    assert_eq!(graph.edge_count(), 8);
+}
+
+#[test]
+fn prune_caps_the_walks_explored() {
+    // a chain of 40 diamonds has 2^40 walks through it
+    let mut graph = HashGraph::new();
+    let mut last = graph.append_handle(b"A");
+    for _ in 0..40 {
+        let top = graph.append_handle(b"C");
+        let bottom = graph.append_handle(b"G");
+        let join = graph.append_handle(b"T");
+        for &(l, r) in [(last, top), (last, bottom), (top, join), (bottom, join)].iter() {
+            graph.create_edge(Edge(l, r));
+        }
+        last = join;
+    }
+
+    // no walk of 200 bases crosses 1000 edges, only the cap marks them
+    let (pruned, report) = prune_complex_regions(&graph, 200, 1000, 64, false);
+    assert!(!report.removed.is_empty());
+    assert!(pruned.edge_count() < graph.edge_count());
+    assert!(pruned.validate().is_empty());
+
+    let (pruned, report) = prune_complex_regions(&graph, 4, 1000, 64, false);
+    assert!(report.removed.is_empty());
+    assert_eq!(pruned.edge_count(), graph.edge_count());
+}
+
+#[test]
+fn normalize_sibling_prefixes_and_suffixes() {
+    let spell = |graph: &HashGraph, name: &[u8]| -> Vec<u8> {
+        let p = graph.name_to_path_handle(name).unwrap();
+        graph.paths[&p]
+            .nodes
+            .iter()
+            .flat_map(|&h| graph.sequence(h))
+            .collect()
+    };
+    let build = |seqs: &[&str]| {
+        let mut graph = HashGraph::new();
+        let h: Vec<Handle> = seqs
+            .iter()
+            .map(|s| graph.append_handle(s.as_bytes()))
+            .collect();
+        for &(l, r) in [(0, 1), (0, 2), (1, 3), (2, 3)].iter() {
+            graph.create_edge(Edge(h[l], h[r]));
+        }
+        let p1 = graph.create_path_handle(b"p1", false);
+        let p2 = graph.create_path_handle(b"p2", false);
+        let p3 = graph.create_path_handle(b"p3", false);
+        for &i in [0, 1, 3].iter() {
+            graph.append_step(&p1, h[i]);
+        }
+        for &i in [0, 2, 3].iter() {
+            graph.append_step(&p2, h[i]);
+        }
+        for &i in [3, 2, 0].iter() {
+            graph.append_step(&p3, h[i].flip());
+        }
+        graph
+    };
+
+    // shared prefix: A -> CGT | CGA -> G
+    let mut graph = build(&["A", "CGT", "CGA", "G"]);
+    let report = normalize(&mut graph);
+    assert_eq!(
+        report,
+        NormalizeReport {
+            merged: 1,
+            divided: 2
+        }
+    );
+    assert_eq!(graph.node_count(), 5);
+    assert_eq!(graph.edge_count(), 5);
+    assert_eq!(spell(&graph, b"p1"), b"ACGTG".to_vec());
+    assert_eq!(spell(&graph, b"p2"), b"ACGAG".to_vec());
+    assert_eq!(spell(&graph, b"p3"), b"CTCGT".to_vec());
+    assert!(graph.validate().is_empty());
+
+    // shared suffix: A -> TG | CG -> T, and identical siblings
+    let mut graph = build(&["A", "TG", "CG", "T"]);
+    let report = normalize(&mut graph);
+    assert_eq!(
+        report,
+        NormalizeReport {
+            merged: 1,
+            divided: 2
+        }
+    );
+    assert_eq!(graph.node_count(), 5);
+    assert_eq!(spell(&graph, b"p1"), b"ATGT".to_vec());
+    assert_eq!(spell(&graph, b"p2"), b"ACGT".to_vec());
+    assert!(graph.validate().is_empty());
+
+    let mut graph = build(&["A", "CG", "CG", "T"]);
+    let report = normalize(&mut graph);
+    assert_eq!(
+        report,
+        NormalizeReport {
+            merged: 1,
+            divided: 0
+        }
+    );
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 2);
+    assert_eq!(spell(&graph, b"p3"), b"ACGT".to_vec());
+    assert_eq!(normalize(&mut graph), NormalizeReport::default());
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn orient_nodes_along_reference() {
+    let mut graph = HashGraph::new();
+    let h: Vec<Handle> = ["AC", "GGT", "T", "CA", "G"]
+        .iter()
+        .map(|s| graph.append_handle(s.as_bytes()))
+        .collect();
+    graph.create_edge(Edge(h[0], h[1].flip()));
+    graph.create_edge(Edge(h[1].flip(), h[2]));
+    graph.create_edge(Edge(h[2], h[3].flip()));
+    let p = graph.create_path_handle(b"ref", false);
+    for &s in [h[0], h[1].flip(), h[2]].iter() {
+        graph.append_step(&p, s);
+    }
+
+    assert_eq!(orient_to_path(&mut graph, b"missing"), None);
+    let report = orient_to_path(&mut graph, b"ref").unwrap();
+    assert_eq!(report.flipped, vec![NodeId::from(2), NodeId::from(4)]);
+    assert!(report.conflicts.is_empty());
+    assert_eq!(graph.paths[&p].nodes, vec![h[0], h[1], h[2]]);
+    assert_eq!(graph.sequence(h[1]), b"ACC");
+    assert_eq!(graph.sequence(h[3]), b"TG");
+    assert!(graph.has_edge(h[0], h[1]) && graph.has_edge(h[1], h[2]) && graph.has_edge(h[2], h[3]));
+    assert!(graph.validate().is_empty());
+
+    // already oriented
+    let report = orient_to_path(&mut graph, b"ref").unwrap();
+    assert_eq!(report, OrientReport::default());
+
+    // a path crossing a node both ways
+    let loop_path = graph.create_path_handle(b"loop", false);
+    for &s in [h[0], h[1].flip(), h[0].flip()].iter() {
+        graph.append_step(&loop_path, s);
+    }
+    let report = orient_to_path(&mut graph, b"loop").unwrap();
+    assert_eq!(report.conflicts, vec![NodeId::from(1)]);
+    // the rest of the graph follows node 2
+    let ids: Vec<NodeId> = vec![2.into(), 3.into(), 4.into()];
+    assert_eq!(report.flipped, ids);
+    assert!(graph.validate().is_empty());
+}
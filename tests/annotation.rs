@@ -0,0 +1,115 @@
+use bstr::BString;
+use fnv::FnvHashSet;
+
+use handlegraph2::{
+    annotation::*,
+    handle::{Edge, Handle, NodeId},
+    hashgraph::HashGraph,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+/*
+paths
+ref: 1+ 2+ 4+
+alt: 4- 3- 1-
+ */
+fn annotated_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    let h1 = graph.create_handle(b"ACGT", 1);
+    let h2 = graph.create_handle(b"GT", 2);
+    let h3 = graph.create_handle(b"TTT", 3);
+    let h4 = graph.create_handle(b"CATCA", 4);
+
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h1, h3));
+    graph.create_edge(Edge(h2, h4));
+    graph.create_edge(Edge(h3, h4));
+
+    let p = graph.create_path_handle(b"ref", false);
+    for &h in [h1, h2, h4].iter() {
+        graph.append_step(&p, h);
+    }
+    let p = graph.create_path_handle(b"alt", false);
+    for &h in [h4, h3, h1].iter() {
+        graph.append_step(&p, h.flip());
+    }
+
+    graph
+}
+
+#[test]
+fn read_bed_and_gff() {
+    let bed = b"track name=genes\nref\t2\t7\tgene1\t0\t+\nalt\t0\t3\n";
+    let intervals = read_bed(&bed[..]).unwrap();
+    assert_eq!(intervals.len(), 2);
+    assert_eq!(intervals[0].path_name, BString::from("ref"));
+    assert_eq!((intervals[0].start, intervals[0].end), (2, 7));
+    assert_eq!(intervals[0].name, BString::from("gene1"));
+    assert_eq!(intervals[1].name, BString::from(""));
+
+    let gff = b"##gff-version 3\nref\tsrc\tgene\t3\t7\t.\t+\t.\tID=gene1;Name=G1\n\
+                ref\tsrc\texon\t1\t2\t.\t+\t.\tParent=gene1\n";
+    let intervals = read_gff(&gff[..]).unwrap();
+    assert_eq!(intervals.len(), 2);
+    assert_eq!((intervals[0].start, intervals[0].end), (2, 7));
+    assert_eq!(intervals[0].name, BString::from("gene1"));
+    assert_eq!(intervals[1].name, BString::from("exon"));
+
+    assert!(read_bed(&b"ref\t7\tX\n"[..]).is_err());
+
+    // only whole words start track and browser lines
+    let bed = b"browser position ref:1-7\ntrack\ntrack_1\t0\t4\tgene2\n";
+    let intervals = read_bed(&bed[..]).unwrap();
+    assert_eq!(intervals.len(), 1);
+    assert_eq!(intervals[0].path_name, BString::from("track_1"));
+}
+
+#[test]
+fn project_intervals_to_nodes() {
+    let graph = annotated_graph();
+    let bed = b"ref\t2\t7\tgene1\nalt\t4\t9\tgene2\nmissing\t0\t1\tnone\n";
+    let intervals = read_bed(&bed[..]).unwrap();
+
+    let projected = project_intervals(&graph, &intervals);
+    let found: Vec<_> = projected
+        .iter()
+        .map(|i| (i.handle, i.start, i.end))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Handle::pack(1, false), 2, 4),
+            (Handle::pack(2, false), 0, 2),
+            (Handle::pack(4, false), 0, 1),
+            (Handle::pack(4, true), 4, 5),
+            (Handle::pack(3, true), 0, 3),
+            (Handle::pack(1, true), 0, 1),
+        ]
+    );
+
+    let mut table = vec![];
+    write_node_table(&mut table, &projected).unwrap();
+    assert_eq!(
+        String::from_utf8(table).unwrap(),
+        "1\t-\t0\t1\tgene2\n1\t+\t2\t4\tgene1\n2\t+\t0\t2\tgene1\n\
+         3\t-\t0\t3\tgene2\n4\t+\t0\t1\tgene1\n4\t-\t4\t5\tgene2\n"
+    );
+}
+
+#[test]
+fn project_nodes_to_path() {
+    let graph = annotated_graph();
+    let nodes: FnvHashSet<NodeId> = vec![1, 2, 3].into_iter().map(NodeId::from).collect();
+
+    let intervals = nodes_to_intervals(&graph, b"ref", &nodes, b"core").unwrap();
+    let found: Vec<_> = intervals.iter().map(|i| (i.start, i.end)).collect();
+    assert_eq!(found, vec![(0, 6)]);
+
+    let intervals = nodes_to_intervals(&graph, b"alt", &nodes, b"core").unwrap();
+    let mut bed = vec![];
+    write_bed(&mut bed, &intervals).unwrap();
+    assert_eq!(String::from_utf8(bed).unwrap(), "alt\t5\t12\tcore\n");
+
+    assert!(nodes_to_intervals(&graph, b"missing", &nodes, b"core").is_none());
+}
@@ -425,6 +425,145 @@ fn remove_path_from_graph() {
     }
 }
 
+#[test]
+fn remove_edge_from_both_nodes() {
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"A");
+    let h2 = graph.append_handle(b"C");
+    let h3 = graph.append_handle(b"G");
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h2.flip(), h3));
+
+    assert!(graph.remove_edge(Edge(h1, h2)));
+    assert!(graph.remove_edge(Edge(h2.flip(), h3)));
+    assert!(!graph.has_edge(h2.flip(), h1.flip()));
+    assert!(!graph.has_edge(h3.flip(), h2));
+    for node in graph.graph.values() {
+        assert!(node.left_edges.is_empty());
+        assert!(node.right_edges.is_empty());
+    }
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn remove_edge_in_either_orientation() {
+    let mut graph = path_graph();
+    let steps: Vec<(&[u8], Vec<Handle>)> = vec![
+        (b"a", vec![H1, H2, H5]),
+        (b"b", vec![H1, H3, H4]),
+        (b"c", vec![H4.flip(), H3.flip(), H1.flip()]),
+        (b"d", vec![H2, H5, H6]),
+    ];
+    for (name, handles) in steps.iter() {
+        let path = graph.create_path_handle(name, false);
+        for &h in handles.iter() {
+            graph.append_step(&path, h);
+        }
+    }
+    // the paths after the hole are looked at too
+    assert!(graph.remove_path(b"a"));
+
+    assert!(graph.remove_edge(Edge(H3.flip(), H1.flip())));
+    assert!(!graph.has_edge(H1, H3));
+    assert!(!graph.has_path(b"b"));
+    assert!(!graph.has_path(b"c"));
+    assert!(graph.has_path(b"d"));
+    assert_eq!(graph.path_count(), 1);
+    assert!(graph.validate().is_empty());
+
+    // nothing left to remove
+    let before = graph.clone();
+    assert!(!graph.remove_edge(Edge(H1, H3)));
+    assert_eq!(graph.graph, before.graph);
+}
+
+#[test]
+fn remove_handle_with_its_paths() {
+    let mut graph = path_graph();
+    let steps: Vec<(&[u8], Vec<Handle>)> = vec![
+        (b"a", vec![H1, H2]),
+        (b"b", vec![H3, H4, H6]),
+        (b"c", vec![H5, H2.flip()]),
+    ];
+    for (name, handles) in steps.iter() {
+        let path = graph.create_path_handle(name, false);
+        for &h in handles.iter() {
+            graph.append_step(&path, h);
+        }
+    }
+    assert!(graph.remove_path(b"a"));
+
+    assert!(graph.remove_handle(H2.id()));
+    assert!(!graph.remove_handle(H2.id()));
+    assert!(!graph.has_path(b"c"));
+    assert!(graph.has_path(b"b"));
+    assert_eq!(graph.graph[&H1.id()].right_edges, vec![H3]);
+    assert!(graph.graph[&H5.id()].left_edges.is_empty());
+    assert!(graph.graph[&H5.id()].occurrences.is_empty());
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn remove_path_from_its_nodes() {
+    let mut graph = path_graph();
+    let path = graph.create_path_handle(b"path-1", false);
+    for &h in [H1, H2, H4].iter() {
+        graph.append_step(&path, h);
+    }
+
+    assert!(graph.remove_path(b"path-1"));
+    assert!(!graph.has_path(b"path-1"));
+    assert!(graph.graph.values().all(|n| n.occurrences.is_empty()));
+    assert!(graph.validate().is_empty());
+
+    // the name can be used again
+    let path = graph.create_path_handle(b"path-1", false);
+    graph.append_step(&path, H3);
+    assert_eq!(graph.name_to_path_handle(b"path-1"), Some(path));
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn removed_path_ids_are_not_reused() {
+    let mut graph = path_graph();
+    let names = [&b"path-1"[..], b"path-2", b"path-3"];
+    let paths: Vec<i64> = names
+        .iter()
+        .map(|name| graph.create_path_handle(name, false))
+        .collect();
+    for (&p, &h) in paths.iter().zip([H1, H2, H3].iter()) {
+        graph.append_step(&p, h);
+    }
+
+    assert!(graph.remove_path(b"path-1"));
+    let p4 = graph.create_path_handle(b"path-4", false);
+    graph.append_step(&p4, H4);
+    assert!(!paths.contains(&p4));
+
+    // the paths that were already there are untouched
+    for (&p, &h) in paths.iter().zip([H1, H2, H3].iter()).skip(1) {
+        assert_eq!(graph.paths[&p].nodes, vec![h]);
+        assert_eq!(graph.graph[&h.id()].occurrences.get(&p), Some(&0));
+    }
+    assert_eq!(graph.name_to_path_handle(b"path-2"), Some(paths[1]));
+    assert_eq!(graph.name_to_path_handle(b"path-3"), Some(paths[2]));
+    assert_eq!(graph.name_to_path_handle(b"path-4"), Some(p4));
+    assert_eq!(graph.path_count(), 3);
+    assert!(graph.validate().is_empty());
+
+    // nor by recorded edits, that are undone on the right path
+    use handlegraph2::hashgraph::RecordedGraph;
+    let before = graph.clone();
+    let mut recorded = RecordedGraph::new(graph);
+    assert!(recorded.remove_path(b"path-2"));
+    assert!(recorded.create_path(b"path-5", false));
+    assert!(recorded.append_step(b"path-5", H5));
+    assert!(recorded.undo() && recorded.undo() && recorded.undo());
+    assert_eq!(recorded.graph().paths, before.paths);
+    assert_eq!(recorded.graph().path_id, before.path_id);
+    assert_eq!(recorded.graph().graph, before.graph);
+}
+
 #[test]
 fn clear_graph() {
     use gfa2::gfa2::GFA2;
@@ -964,3 +1103,832 @@ fn graph_divide_handle() {
 
     assert_eq!(expected_handles, handles);
 }
+
+#[test]
+fn validate_consistent_graph() {
+    let mut graph = path_graph();
+    graph.create_edge(Edge(H2, H2.flip()));
+    graph.create_edge(Edge(H4.flip(), H4));
+    graph.create_edge(Edge(H5, H5));
+
+    let p1 = graph.create_path_handle(b"path-1", false);
+    graph.append_step(&p1, H1);
+    graph.append_step(&p1, H3);
+    graph.append_step(&p1, H4.flip());
+
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn validate_reports_violations() {
+    use handlegraph2::hashgraph::Violation;
+
+    let mut graph = path_graph();
+    let p1 = graph.create_path_handle(b"path-1", false);
+    graph.append_step(&p1, H1);
+    graph.append_step(&p1, H2);
+
+    // an edge stored on one side only
+    graph.get_node_mut(&H3.id()).unwrap().right_edges.push(H5);
+    // a step on a node that doesn't exist
+    graph
+        .paths
+        .get_mut(&p1)
+        .unwrap()
+        .nodes
+        .push(Handle::pack(9, false));
+    // an occurrence pointing to the wrong step
+    graph
+        .get_node_mut(&H2.id())
+        .unwrap()
+        .occurrences
+        .insert(p1, 0);
+
+    let violations = graph.validate();
+
+    assert_eq!(
+        violations,
+        vec![
+            Violation::UnmirroredEdge { from: H3, to: H5 },
+            Violation::MissingStepNode {
+                path: p1,
+                index: 2,
+                node: NodeId::from(9),
+            },
+            Violation::InvalidOccurrence {
+                node: H2.id(),
+                path: p1,
+                index: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn path_position_index() {
+    use handlegraph2::hashgraph::PathStep::*;
+
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"ABCD");
+    let h2 = graph.append_handle(b"EFGHIJKLMN");
+    let h3 = graph.append_handle(b"OPQ");
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h2, h3));
+
+    let indexed = graph.create_path_handle(b"indexed", false);
+    let scanned = graph.create_path_handle(b"scanned", false);
+    for &p in [indexed, scanned].iter() {
+        graph.append_step(&p, h2);
+    }
+    assert!(graph.index_path_positions(&indexed));
+
+    // both paths must always agree
+    let check = |graph: &HashGraph| {
+        let len = graph.path_bases_len(&scanned);
+        assert_eq!(graph.path_bases_len(&indexed), len);
+        for ix in 0..graph.step_count(&scanned) {
+            assert_eq!(
+                graph.position_of_step(&Step(indexed, ix)),
+                graph.position_of_step(&Step(scanned, ix))
+            );
+        }
+        for pos in 0..=len.unwrap() {
+            let a = graph.step_at_position(&indexed, pos).unwrap();
+            let b = graph.step_at_position(&scanned, pos).unwrap();
+            assert_eq!(a.index(), b.index());
+        }
+    };
+
+    for &p in [indexed, scanned].iter() {
+        graph.append_step(&p, h3);
+        graph.prepend_step(&p, h1);
+    }
+    check(&graph);
+    assert_eq!(graph.path_bases_len(&indexed), Some(17));
+    assert_eq!(graph.position_of_step(&Step(indexed, 2)), Some(14));
+    assert_eq!(graph.step_at_position(&indexed, 13), Some(Step(indexed, 1)));
+    assert_eq!(graph.step_at_position(&indexed, 17), Some(End(indexed)));
+
+    graph.modify_handle(h2.id(), b"EF");
+    check(&graph);
+    assert_eq!(graph.path_bases_len(&indexed), Some(9));
+
+    graph.divide_handle(h1, vec![1, 3]);
+    check(&graph);
+    assert_eq!(graph.step_count(&indexed), 5);
+    assert_eq!(graph.position_of_step(&Step(indexed, 2)), Some(3));
+
+    // the index isn't part of the equality of paths
+    let mut path = graph.paths[&indexed].clone();
+    assert_eq!(path.positions(), Some(&[0, 1, 3, 4, 6, 9][..]));
+    let indexed_path = path.clone();
+    path.clear_positions();
+    assert!(!path.is_indexed());
+    assert_eq!(path, indexed_path);
+}
+
+#[test]
+fn compact_node_ids() {
+    let mut graph = path_graph();
+    let p1 = graph.create_path_handle(b"path-1", false);
+    for &h in [H1, H3, H4.flip()].iter() {
+        graph.append_step(&p1, h);
+    }
+    graph.remove_handle(H2.id());
+    let h7 = graph.append_handle(b"7");
+    graph.create_edge(Edge(H6, h7));
+
+    let map = graph.compact_ids(Some(&[NodeId::from(7), NodeId::from(2)]));
+    let expected: Vec<(u64, u64)> = vec![(7, 1), (1, 2), (3, 3), (4, 4), (5, 5), (6, 6)];
+    let mut map: Vec<(u64, u64)> = map.into_iter().map(|(a, b)| (a.into(), b.into())).collect();
+    map.sort_by_key(|&(_, new)| new);
+    assert_eq!(map, expected);
+
+    assert_eq!(graph.min_node_id(), NodeId::from(1));
+    assert_eq!(graph.max_node_id(), NodeId::from(6));
+    assert_eq!(graph.sequence(Handle::pack(1, false)), b"7");
+    assert!(graph.has_edge(Handle::pack(6, false), Handle::pack(1, false)));
+    assert!(graph.has_edge(Handle::pack(2, false), Handle::pack(3, false)));
+    assert_eq!(
+        graph.paths[&p1].nodes,
+        vec![Handle::pack(2, false), H3, H4.flip()]
+    );
+    assert!(graph.validate().is_empty());
+
+    // dangling edges and steps are dropped
+    let missing = Handle::pack(9, false);
+    graph
+        .graph
+        .get_mut(&H3.id())
+        .unwrap()
+        .right_edges
+        .push(missing);
+    graph.paths.get_mut(&p1).unwrap().nodes.insert(1, missing);
+    graph.index_all_path_positions();
+    assert!(!graph.validate().is_empty());
+
+    graph.compact_ids(None);
+    assert_eq!(
+        graph.paths[&p1].nodes,
+        vec![Handle::pack(2, false), H3, H4.flip()]
+    );
+    assert_eq!(graph.path_bases_len(&p1), Some(3));
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn divide_reverse_handle_at_many_offsets() {
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"AAC");
+    let h2 = graph.append_handle(b"GTTCA");
+    let h3 = graph.append_handle(b"CC");
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h2, h3));
+    let path = graph.create_path_handle(b"path-1", false);
+    for &h in [h3.flip(), h2.flip(), h1.flip(), h1, h2].iter() {
+        graph.append_step(&path, h);
+    }
+    graph.index_all_path_positions();
+
+    // TGAAC split into T, GA, AC
+    let pieces = graph.divide_handle(h2.flip(), vec![1, 3]);
+    let sequences: Vec<Vec<u8>> = pieces.iter().map(|&h| graph.sequence(h)).collect();
+    assert_eq!(
+        sequences,
+        vec![b"T".to_vec(), b"GA".to_vec(), b"AC".to_vec()]
+    );
+    assert!(pieces.iter().all(|h| h.is_reverse()));
+    assert_eq!(pieces[2], h2.flip());
+    assert!(graph.has_edge(h3.flip(), pieces[0]));
+    assert!(graph.has_edge(pieces[2], h1.flip()));
+
+    // both steps on the node are replaced, in their own orientation
+    let steps: Vec<Handle> = graph.paths[&path].nodes.clone();
+    let expected: Vec<Handle> = vec![h3.flip()]
+        .into_iter()
+        .chain(pieces.iter().copied())
+        .chain(vec![h1.flip(), h1])
+        .chain(pieces.iter().rev().map(|h| h.flip()))
+        .collect();
+    assert_eq!(steps, expected);
+    assert_eq!(graph.path_bases_len(&path), Some(18));
+    assert_eq!(
+        graph.step_at_position(&path, 14),
+        Some(PathStep::Step(path, 6))
+    );
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn divide_reverse_handle_with_paths() {
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"ACGTA");
+    let h2 = graph.append_handle(b"GG");
+    graph.create_edge(Edge(h1, h2));
+    let p1 = graph.create_path_handle(b"p1", false);
+    let p2 = graph.create_path_handle(b"p2", false);
+    for &h in [h1, h2, h1].iter() {
+        graph.append_step(&p1, h);
+    }
+    for &h in [h2.flip(), h1.flip()].iter() {
+        graph.append_step(&p2, h);
+    }
+
+    // TACGT split into TA, CGT
+    let pieces = graph.divide_handle(h1.flip(), vec![2]);
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(graph.sequence(pieces[0]), b"TA");
+    assert_eq!(graph.sequence(pieces[1]), b"CGT");
+    assert!(graph.has_edge(pieces[0], pieces[1]));
+    assert!(graph.has_edge(pieces[0].flip(), h2));
+
+    let spell = |graph: &HashGraph, p| -> Vec<u8> {
+        graph.paths[&p]
+            .nodes
+            .iter()
+            .flat_map(|&h| graph.sequence(h))
+            .collect()
+    };
+    assert_eq!(spell(&graph, p1), b"ACGTAGGACGTA".to_vec());
+    assert_eq!(spell(&graph, p2), b"CCTACGT".to_vec());
+    assert_eq!(graph.step_count(&p1), 5);
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn apply_orientation_flips_node_in_place() {
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"AAC");
+    let h2 = graph.append_handle(b"GT");
+    let h3 = graph.append_handle(b"T");
+    graph.create_edge(Edge(h1, h2.flip()));
+    graph.create_edge(Edge(h2.flip(), h3));
+    graph.create_edge(Edge(h2, h2.flip()));
+    let p = graph.create_path_handle(b"p", false);
+    for &h in [h1, h2.flip(), h3, h2].iter() {
+        graph.append_step(&p, h);
+    }
+    let spell = |graph: &HashGraph| -> Vec<u8> {
+        graph.paths[&p]
+            .nodes
+            .iter()
+            .flat_map(|&h| graph.sequence(h))
+            .collect()
+    };
+    let before = spell(&graph);
+
+    assert_eq!(graph.apply_orientation(h2), h2);
+    assert_eq!(graph.apply_orientation(h2.flip()), h2);
+    assert_eq!(graph.sequence(h2), b"AC");
+    assert!(graph.has_edge(h1, h2));
+    assert!(graph.has_edge(h2, h3));
+    assert!(graph.has_edge(h2.flip(), h2));
+    assert_eq!(graph.edge_count(), 3);
+    assert_eq!(graph.paths[&p].nodes, vec![h1, h2, h3, h2.flip()]);
+    assert_eq!(spell(&graph), before);
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn merge_handles_with_orientations() {
+    let mut graph = HashGraph::new();
+    let h: Vec<Handle> = ["ACG", "CGT", "ACG", "T", "G", "A"]
+        .iter()
+        .map(|s| graph.append_handle(s.as_bytes()))
+        .collect();
+    let (h1, h2, h3, h4, h5, h6) = (h[0], h[1], h[2], h[3], h[4], h[5]);
+    graph.create_edge(Edge(h4, h1));
+    graph.create_edge(Edge(h5, h2.flip()));
+    graph.create_edge(Edge(h3, h6));
+    graph.create_edge(Edge(h1, h3));
+    let p = graph.create_path_handle(b"p", false);
+    for &s in [h4, h1, h3, h6].iter() {
+        graph.append_step(&p, s);
+    }
+    let q = graph.create_path_handle(b"q", false);
+    for &s in [h2, h5.flip()].iter() {
+        graph.append_step(&q, s);
+    }
+
+    assert_eq!(graph.merge_handles(&[]), None);
+    assert_eq!(graph.merge_handles(&[h1, h2.flip(), h3, h3]), Some(h1));
+    assert_eq!(graph.node_count(), 4);
+    assert!(graph.get_node(&h2.id()).is_none() && graph.get_node(&h3.id()).is_none());
+    assert_eq!(graph.edge_count(), 4);
+    assert!(graph.has_edge(h4, h1));
+    assert!(graph.has_edge(h5, h1));
+    assert!(graph.has_edge(h1, h6));
+    assert!(graph.has_edge(h1, h1));
+    assert_eq!(graph.paths[&p].nodes, vec![h4, h1, h1, h6]);
+    assert_eq!(graph.paths[&q].nodes, vec![h1.flip(), h5.flip()]);
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn recorded_edits_undo_redo_and_replay() {
+    use handlegraph2::hashgraph::{read_log, RecordedGraph};
+
+    let same = |a: &HashGraph, b: &HashGraph| {
+        a.graph == b.graph
+            && a.paths == b.paths
+            && a.path_id == b.path_id
+            && a.max_id == b.max_id
+            && a.min_id == b.min_id
+    };
+
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"ACGT");
+    let h2 = graph.append_handle(b"T");
+    let h3 = graph.append_handle(b"GA");
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h2, h3));
+    let p = graph.create_path_handle(b"p", false);
+    for &h in [h1, h2, h3].iter() {
+        graph.append_step(&p, h);
+    }
+    let original = graph.clone();
+
+    let mut recorded = RecordedGraph::new(graph);
+    assert!(recorded.modify_handle(h2.id(), b"CC"));
+    // a name that would add an edit to the log is refused
+    assert!(!recorded.begin("split\nremove_handle\t1"));
+    assert!(recorded.begin("split"));
+    let pieces = recorded.divide_handle(h1.flip(), vec![1]).unwrap();
+    assert_eq!(pieces.len(), 2);
+    assert!(recorded.create_edge(Edge(h1, h3)));
+    assert!(recorded.rewrite_segment(b"p", 2, 2, vec![h3.flip()]));
+    assert!(recorded.commit());
+    assert!(recorded.remove_handle(h2.id()));
+    // rejected edits are not recorded
+    assert!(!recorded.remove_handle(h2.id()));
+    assert_eq!(recorded.append_handle(b""), None);
+    let edited = recorded.graph().clone();
+
+    let names: Vec<_> = recorded.transactions().map(|t| t.name.clone()).collect();
+    assert_eq!(names, vec!["modify_handle", "split", "remove_handle"]);
+
+    assert!(recorded.begin("discarded"));
+    recorded.apply_orientation(h3.flip());
+    recorded.merge_handles(&[h3]);
+    assert!(!recorded.undo());
+    assert!(recorded.rollback());
+    assert!(same(recorded.graph(), &edited));
+
+    assert!(recorded.undo() && recorded.undo() && recorded.undo());
+    assert!(!recorded.undo());
+    assert!(same(recorded.graph(), &original));
+    assert!(recorded.redo() && recorded.redo() && recorded.redo());
+    assert!(!recorded.redo());
+    assert!(same(recorded.graph(), &edited));
+    assert!(recorded.graph().validate().is_empty());
+
+    let mut log = vec![];
+    recorded.write_log(&mut log).unwrap();
+    let transactions = read_log(&log[..]).unwrap();
+    assert_eq!(
+        transactions,
+        recorded.transactions().cloned().collect::<Vec<_>>()
+    );
+
+    let mut copy = RecordedGraph::new(original.clone());
+    assert!(copy.replay(&transactions));
+    assert!(same(copy.graph(), &edited));
+    // the log doesn't apply twice: node 2 is already gone
+    assert!(!copy.replay(&transactions[2..]));
+    assert!(same(copy.graph(), &edited));
+
+    // a new edit drops the undone ones
+    recorded.undo();
+    recorded.create_edge(Edge(h3, h1));
+    assert!(!recorded.redo());
+    assert!(read_log(&b"remove_handle\tx\n"[..]).is_err());
+}
+
+#[test]
+fn recorded_edits_of_every_kind_undo_and_redo() {
+    use handlegraph2::hashgraph::{Edit, RecordedGraph};
+
+    let same = |a: &HashGraph, b: &HashGraph| {
+        a.graph == b.graph
+            && a.paths == b.paths
+            && a.paths
+                .values()
+                .all(|p| p.positions() == b.paths[&p.path_id].positions())
+            && a.path_id == b.path_id
+            && a.max_id == b.max_id
+            && a.min_id == b.min_id
+    };
+
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"ACGT");
+    let h2 = graph.append_handle(b"T");
+    let h3 = graph.append_handle(b"GA");
+    let h4 = graph.append_handle(b"GA");
+    graph.create_edge(Edge(h1, h2));
+    graph.create_edge(Edge(h2, h3));
+    graph.create_edge(Edge(h2, h4));
+    let p = graph.create_path_handle(b"p", false);
+    for &h in [h1, h2, h3].iter() {
+        graph.append_step(&p, h);
+    }
+    let q = graph.create_path_handle(b"q", false);
+    for &h in [h4.flip(), h2.flip(), h1.flip()].iter() {
+        graph.append_step(&q, h);
+    }
+    graph
+        .paths
+        .get_mut(&q)
+        .unwrap()
+        .index_positions(&graph.graph);
+
+    let edits = vec![
+        Edit::ModifyHandle {
+            node: h2.id(),
+            sequence: "CC".into(),
+        },
+        Edit::DivideHandle {
+            handle: h1.flip(),
+            offsets: vec![1, 3],
+        },
+        Edit::CreateHandle {
+            node: NodeId::from(20),
+            sequence: "A".into(),
+        },
+        Edit::CreateEdge(Edge(h3, Handle::pack(20, false))),
+        Edit::ModifyEdge {
+            edge: Edge(h2, h4),
+            left: None,
+            right: Some(h4.flip()),
+        },
+        Edit::ApplyOrientation(h3.flip()),
+        Edit::MergeHandles(vec![h3.flip(), h4]),
+        Edit::CreatePath {
+            path: "r".into(),
+            is_circular: false,
+        },
+        Edit::AppendStep {
+            path: "r".into(),
+            handle: h2,
+        },
+        Edit::RewriteSegment {
+            path: "p".into(),
+            begin: 0,
+            end: 1,
+            segment: vec![h2],
+        },
+        Edit::ModifyPath {
+            path: "r".into(),
+            handles: vec![h2, h2],
+        },
+        Edit::RemoveEdge(Edge(h2, h3.flip())),
+        Edit::RemovePath { path: "r".into() },
+        Edit::RemoveHandle { node: h2.id() },
+    ];
+
+    let mut recorded = RecordedGraph::new(graph);
+    for edit in edits {
+        let before = recorded.graph().clone();
+        assert!(recorded.apply(edit.clone()).is_some(), "{}", edit);
+        let after = recorded.graph().clone();
+        assert!(recorded.undo());
+        assert!(same(recorded.graph(), &before), "{}", edit);
+        assert!(recorded.redo());
+        assert!(same(recorded.graph(), &after), "{}", edit);
+    }
+}
+
+#[test]
+fn recorded_edits_that_cant_be_logged_are_refused() {
+    use handlegraph2::hashgraph::{read_log, RecordedGraph};
+
+    let mut graph = HashGraph::new();
+    let h1 = graph.append_handle(b"ACGT");
+    let mut recorded = RecordedGraph::new(graph);
+
+    assert_eq!(recorded.divide_handle(h1, vec![]), None);
+    assert!(!recorded.create_path(b"a\tb", false));
+    assert!(!recorded.create_path(b"a\nb", false));
+    assert!(!recorded.modify_handle(h1.id(), b"AC\rGT"));
+    assert_eq!(recorded.transactions().count(), 0);
+
+    assert!(recorded.create_path(b"a b", false));
+    assert_eq!(
+        recorded.divide_handle(h1, vec![2]).map(|h| h.len()),
+        Some(2)
+    );
+    let mut log = vec![];
+    recorded.write_log(&mut log).unwrap();
+    let transactions = read_log(&log[..]).unwrap();
+    assert_eq!(
+        transactions,
+        recorded.transactions().cloned().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn observers_receive_graph_events() {
+    use handlegraph2::hashgraph::GraphEvent::{self, *};
+    use std::sync::{Arc, Mutex};
+
+    let events: Arc<Mutex<Vec<GraphEvent>>> = Arc::new(Mutex::new(vec![]));
+    let mut graph = HashGraph::new();
+    let log = events.clone();
+    let id = graph.subscribe(Box::new(move |e: &GraphEvent| {
+        log.lock().unwrap().push(e.clone())
+    }));
+    let take = || std::mem::take(&mut *events.lock().unwrap());
+
+    let h1 = graph.append_handle(b"ACGT");
+    let h2 = graph.append_handle(b"T");
+    graph.create_edge(Edge(h2.flip(), h1.flip()));
+    // already there
+    graph.create_edge(Edge(h1, h2));
+    let p = graph.create_path_handle(b"p", false);
+    graph.append_step(&p, h1);
+    assert_eq!(
+        take(),
+        vec![
+            NodeCreated(h1.id()),
+            NodeCreated(h2.id()),
+            EdgeCreated(Edge::edge_handle(h1, h2)),
+            PathCreated(p),
+            PathStepsChanged(p),
+        ]
+    );
+
+    graph.modify_handle(h2.id(), b"T");
+    graph.modify_handle(h2.id(), b"GG");
+    assert_eq!(take(), vec![NodeResequenced(h2.id())]);
+
+    let pieces = graph.divide_handle(h1, vec![2]);
+    assert_eq!(
+        take(),
+        vec![
+            NodeCreated(pieces[1].id()),
+            EdgeRemoved(Edge::edge_handle(h1, h2)),
+            EdgeCreated(Edge::edge_handle(pieces[1], h2)),
+            EdgeCreated(Edge::edge_handle(pieces[0], pieces[1])),
+            PathStepsChanged(p),
+            NodeDivided {
+                node: h1.id(),
+                pieces: pieces.clone()
+            },
+        ]
+    );
+
+    // observers are not cloned
+    let mut copy = graph.clone();
+    copy.remove_handle(h2.id());
+    assert!(take().is_empty());
+
+    graph.remove_handle(pieces[1].id());
+    assert_eq!(
+        take(),
+        vec![
+            PathRemoved(p),
+            EdgeRemoved(Edge::edge_handle(pieces[0], pieces[1])),
+            EdgeRemoved(Edge::edge_handle(pieces[1], h2)),
+            NodeRemoved(pieces[1].id()),
+        ]
+    );
+
+    // changes that are not made edit by edit are reported as a reset
+    graph.compact_ids(None);
+    assert_eq!(take(), vec![Reset]);
+
+    let mut recorded = handlegraph2::hashgraph::RecordedGraph::new(graph);
+    assert!(recorded.modify_handle(h2.id(), b"CA"));
+    assert_eq!(take(), vec![NodeResequenced(h2.id())]);
+    assert!(recorded.undo());
+    assert_eq!(take(), vec![Reset]);
+    assert!(recorded.redo());
+    assert_eq!(take(), vec![Reset]);
+    assert!(recorded.begin("empty"));
+    assert!(recorded.rollback());
+    assert!(take().is_empty());
+    let mut graph = recorded.into_inner();
+
+    assert!(graph.unsubscribe(id).is_some());
+    assert!(graph.unsubscribe(id).is_none());
+    graph.clear_graph();
+    assert!(take().is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_iteration_matches_sequential() {
+    use rayon::prelude::*;
+
+    let mut graph = HashGraph::new();
+    let handles: Vec<Handle> = (0..200)
+        .map(|i| graph.append_handle(&b"ACGT"[..1 + i % 4]))
+        .collect();
+    for (i, &h) in handles.iter().enumerate() {
+        graph.create_edge(Edge(h, handles[(i * 7 + 3) % 200]));
+        graph.create_edge(Edge(h.flip(), handles[(i * 13) % 200]));
+    }
+    for p in 0..5 {
+        let path = graph.create_path_handle(format!("p{}", p).as_bytes(), false);
+        for &h in handles.iter().skip(p).step_by(3) {
+            graph.append_step(&path, h);
+        }
+    }
+
+    let mut seq_handles: Vec<Handle> = graph.all_handles().collect();
+    seq_handles.sort();
+    assert_eq!(par_collect_sorted(graph.par_all_handles()), seq_handles);
+
+    let mut seq_edges: Vec<Edge> = graph.all_edges().collect();
+    seq_edges.sort();
+    assert_eq!(par_collect_sorted(graph.par_all_edges()), seq_edges);
+
+    let mut seq_paths: Vec<i64> = graph.paths_iter().copied().collect();
+    seq_paths.sort();
+    assert_eq!(par_collect_sorted(graph.par_paths()), seq_paths);
+    for path in seq_paths.iter() {
+        let steps: Vec<Handle> = graph.par_steps(path).collect();
+        assert_eq!(steps, graph.paths[path].nodes);
+    }
+
+    let lengths = par_reduce_by_key(
+        graph.par_all_handles().map(|h| (graph.node_len(h), 1)),
+        |a, b| a + b,
+    );
+    let expected: Vec<(usize, usize)> = vec![(1, 50), (2, 50), (3, 50), (4, 50)];
+    assert_eq!(lengths.into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn concurrent_builder_matches_sequential() {
+    use handlegraph2::hashgraph::{BuildError, GraphBuilder};
+    use std::sync::Arc;
+
+    let seqs = [&b"A"[..], b"CG", b"TTA", b"GATC"];
+    let node = |i: u64| Handle::pack(i, false);
+    let edges: Vec<Edge> = (1..200u64)
+        .flat_map(|i| {
+            vec![
+                Edge(node(i), node(i + 1)),
+                Edge(node(i + 1).flip(), node(i).flip()),
+                Edge(node(i), Handle::pack(i / 2 + 1, i % 3 == 0)),
+            ]
+        })
+        .collect();
+    let paths: Vec<Vec<Handle>> = (0..5u64)
+        .map(|p| (1..=200u64).skip(p as usize).step_by(3).map(node).collect())
+        .collect();
+
+    let mut expected = HashGraph::new();
+    for i in 1..=200u64 {
+        expected.create_handle(seqs[i as usize % 4], i);
+    }
+    for &Edge(l, r) in edges.iter() {
+        if !expected.has_edge(l, r) {
+            expected.create_edge(Edge(l, r));
+        }
+    }
+    for (p, steps) in paths.iter().enumerate() {
+        let path = expected.create_path_handle(format!("p{}", p).as_bytes(), false);
+        for &h in steps.iter() {
+            expected.append_step(&path, h);
+        }
+    }
+
+    // the edges and paths are pushed before the nodes they use
+    let builder = Arc::new(GraphBuilder::with_shards(4));
+    let threads: Vec<_> = (0..4u64)
+        .map(|t| {
+            let builder = builder.clone();
+            let edges = edges.clone();
+            let paths = paths.clone();
+            std::thread::spawn(move || {
+                for e in edges.iter().skip(t as usize).step_by(4) {
+                    builder.add_edge(*e);
+                }
+                for (p, steps) in paths.iter().enumerate().rev() {
+                    if p as u64 % 4 == t {
+                        builder.add_path(format!("p{}", p).as_bytes(), false, steps.clone());
+                    }
+                }
+                for i in (1..=200u64).filter(|i| i % 4 == t) {
+                    assert!(builder.add_node(i, seqs[i as usize % 4]));
+                }
+            })
+        })
+        .collect();
+    threads.into_iter().for_each(|t| t.join().unwrap());
+
+    let mut graph = Arc::try_unwrap(builder).ok().unwrap().finish().unwrap();
+    for g in [&mut graph, &mut expected].iter_mut() {
+        for node in g.graph.values_mut() {
+            node.left_edges.sort();
+            node.right_edges.sort();
+        }
+    }
+    assert_eq!(graph.min_id, expected.min_id);
+    assert_eq!(graph.max_id, expected.max_id);
+    assert_eq!(graph.graph, expected.graph);
+    assert_eq!(graph.path_id, expected.path_id);
+    assert_eq!(graph.paths, expected.paths);
+
+    let builder = GraphBuilder::new();
+    assert!(builder.add_node(3u64, b"A"));
+    assert!(!builder.add_node(3u64, b"C"));
+    assert_eq!(
+        builder.finish().err(),
+        Some(BuildError::DuplicateNode(NodeId::from(3u64)))
+    );
+
+    // empty sequences are refused, like `create_handle` does
+    let builder = GraphBuilder::new();
+    assert!(builder.add_node(1u64, b"A"));
+    assert!(!builder.add_node(4u64, b""));
+    assert!(!builder.add_node(2u64, b""));
+    assert_eq!(
+        builder.finish().err(),
+        Some(BuildError::EmptyNode(NodeId::from(2u64)))
+    );
+
+    let builder = GraphBuilder::new();
+    builder.add_node(1u64, b"A");
+    builder.add_edge(Edge(node(1), node(7)));
+    builder.add_path(b"x", false, vec![node(5)]);
+    assert_eq!(
+        builder.finish().err(),
+        Some(BuildError::MissingNode(NodeId::from(5u64)))
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_gfa_lines_match_sequential() {
+    use gfa2::{
+        gfa1::{Line as Line1, GFA},
+        parser_gfa1::GFAParser,
+    };
+    use handlegraph2::hashgraph::GraphBuilder;
+    use std::collections::HashMap;
+
+    let parser = GFAParser::new();
+    let gfa: GFA<usize, ()> = parser.parse_file("./tests/gfa1_files/lil.gfa").unwrap();
+    // the paths come in reverse name order
+    let lines: Vec<_> = gfa
+        .segments
+        .iter()
+        .cloned()
+        .map(Line1::Segment)
+        .chain(gfa.links.iter().cloned().map(Line1::Link))
+        .chain(gfa.paths.iter().rev().cloned().map(Line1::Path))
+        .collect();
+
+    let mut expected = HashGraph::new();
+    fill_gfa_lines(&mut expected, lines.iter().cloned().map(Ok)).unwrap();
+    let builder = GraphBuilder::new();
+    par_fill_gfa_lines(&builder, lines.into_iter().map(Ok).collect::<Vec<_>>()).unwrap();
+    let mut graph = builder.finish().unwrap();
+
+    // paths get their ids in file order, or in name order in parallel
+    let names = |g: &HashGraph| -> Vec<Vec<u8>> {
+        (0..g.paths.len() as i64)
+            .map(|p| g.paths[&p].name.to_vec())
+            .collect()
+    };
+    assert_eq!(
+        names(&expected),
+        vec![b"z".to_vec(), b"y".to_vec(), b"x".to_vec()]
+    );
+    assert_eq!(
+        names(&graph),
+        vec![b"x".to_vec(), b"y".to_vec(), b"z".to_vec()]
+    );
+
+    // the same path has another id in each graph
+    let ids: HashMap<i64, i64> = graph
+        .paths
+        .iter()
+        .map(|(&p, path)| (p, expected.path_id[&path.name.to_vec()]))
+        .collect();
+    for node in graph.graph.values_mut() {
+        node.occurrences = node
+            .occurrences
+            .iter()
+            .map(|(p, &ix)| (ids[p], ix))
+            .collect();
+    }
+    for g in [&mut graph, &mut expected].iter_mut() {
+        for node in g.graph.values_mut() {
+            node.left_edges.sort();
+            node.right_edges.sort();
+        }
+    }
+    assert_eq!(graph.graph.len(), 15);
+    assert_eq!(graph.min_id, expected.min_id);
+    assert_eq!(graph.max_id, expected.max_id);
+    assert_eq!(graph.graph, expected.graph);
+    for (name, p) in expected.path_id.iter() {
+        assert_eq!(
+            graph.paths[&graph.path_id[name]].nodes,
+            expected.paths[p].nodes
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use bstr::BString;
+
+use handlegraph2::{
+    handle::{Edge, Handle},
+    handlegraph::*,
+    hashgraph::HashGraph,
+    pathgraph::PathHandleGraph,
+    vcf::*,
+};
+
+fn path_sequence(graph: &HashGraph, name: &[u8]) -> BString {
+    let path = graph.name_to_path_handle(name).unwrap();
+    graph.paths[&path]
+        .nodes
+        .iter()
+        .flat_map(|&h| graph.sequence(h))
+        .collect::<Vec<u8>>()
+        .into()
+}
+
+#[test]
+fn read_fasta_and_vcf() {
+    let fasta = b">chr1 first contig\nACGTA\nCGTAC\n\n>chr2\nTTT\n";
+    let contigs = read_fasta(&fasta[..]).unwrap();
+    assert_eq!(contigs.len(), 2);
+    assert_eq!(contigs[0].name, BString::from("chr1"));
+    assert_eq!(contigs[0].sequence, BString::from("ACGTACGTAC"));
+    assert_eq!(contigs[1].sequence, BString::from("TTT"));
+    assert!(read_fasta(&b"ACGT\n"[..]).is_err());
+
+    let text = b"##fileformat=VCFv4.2
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ts2
+chr1\t3\tsnp\tG\tT,C\t.\tPASS\tDP=10\tDP:GT\t5:0|1\t3:2/.
+chr1\t5\tsv\tA\t<DEL>\t.\tPASS\tSVTYPE=DEL;END=7\tGT\t1\t0
+";
+    let vcf = read_vcf(&text[..]).unwrap();
+    assert_eq!(vcf.samples, vec![BString::from("s1"), BString::from("s2")]);
+    assert_eq!(vcf.records.len(), 2);
+
+    let snp = &vcf.records[0];
+    assert_eq!(snp.pos, 3);
+    assert_eq!(
+        snp.alternatives,
+        vec![BString::from("T"), BString::from("C")]
+    );
+    assert_eq!(snp.info_value(b"DP"), Some(&b"10"[..]));
+    assert_eq!(
+        snp.genotypes,
+        vec![
+            Genotype {
+                alleles: vec![Some(0), Some(1)],
+                phased: true
+            },
+            Genotype {
+                alleles: vec![Some(2), None],
+                phased: false
+            },
+        ]
+    );
+    assert_eq!(vcf.records[1].info_value(b"END"), Some(&b"7"[..]));
+    assert!(vcf.records[1].genotypes[0].phased);
+
+    assert!(read_vcf(&b"chr1\t0\t.\tA\tC\t.\t.\t.\n"[..]).is_err());
+}
+
+#[test]
+fn graph_from_reference_and_variants() {
+    let contigs = vec![Contig {
+        name: "chr1".into(),
+        sequence: "ACGTACGTAC".into(),
+    }];
+    let text = b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ts2
+chr1\t3\tsnp\tG\tT\t.\t.\t.\tGT\t0|1\t1|1
+chr1\t5\tdel\tACG\tA\t.\t.\t.\tGT\t1|0\t0/1
+chr1\t6\toverlap\tC\tG\t.\t.\t.\tGT\t1|1\t1|1
+chr1\t9\tins\tA\tATT\t.\t.\t.\tGT\t0|0\t1|0
+chr1\t4\twrong\tG\tC\t.\t.\t.\tGT\t1|1\t1|1
+chr2\t1\tunknown\tA\tC\t.\t.\t.\tGT\t1|1\t1|1
+";
+    let vcf = read_vcf(&text[..]).unwrap();
+
+    let VcfGraph { graph, skipped } = graph_from_vcf(&contigs, &vcf, false);
+    assert_eq!(skipped, vec![2, 4, 5]);
+    assert_eq!(graph.path_count(), 1);
+    assert_eq!(path_sequence(&graph, b"chr1"), BString::from("ACGTACGTAC"));
+
+    // AC G TA CG TA C, plus T and TT
+    assert_eq!(graph.node_count(), 8);
+    let mut edges: Vec<(u64, u64)> = graph
+        .all_edges()
+        .map(|Edge(l, r)| (u64::from(l.id()), u64::from(r.id())))
+        .map(|(l, r)| (l.min(r), l.max(r)))
+        .collect();
+    edges.sort();
+    assert_eq!(
+        edges,
+        vec![
+            (1, 2),
+            (1, 7),
+            (2, 3),
+            (3, 4),
+            (3, 5),
+            (3, 7),
+            (4, 5),
+            (5, 6),
+            (5, 8),
+            (6, 8)
+        ]
+    );
+
+    let VcfGraph { graph, .. } = graph_from_vcf(&contigs, &vcf, true);
+    assert_eq!(graph.path_count(), 5);
+    let haplotypes: Vec<BString> = [&b"s1#1#chr1"[..], b"s1#2#chr1", b"s2#1#chr1", b"s2#2#chr1"]
+        .iter()
+        .map(|name| path_sequence(&graph, name))
+        .collect();
+    assert_eq!(
+        haplotypes,
+        vec![
+            BString::from("ACGTATAC"),
+            BString::from("ACTTACGTAC"),
+            BString::from("ACTTACGTATTC"),
+            BString::from("ACTTACGTAC"),
+        ]
+    );
+    assert!(graph.validate().is_empty());
+}
+
+#[test]
+fn graph_joins_adjacent_alleles() {
+    let contigs = vec![Contig {
+        name: "chr1".into(),
+        sequence: "ACGTACGTAC".into(),
+    }];
+    let text = b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1
+chr1\t3\tsnp1\tG\tT\t.\t.\t.\tGT\t0|1
+chr1\t4\tsnp2\tT\tA\t.\t.\t.\tGT\t1|0
+chr1\t5\tdel\tACG\tA\t.\t.\t.\tGT\t0|1
+chr1\t8\tsnp3\tT\tG\t.\t.\t.\tGT\t0|0
+";
+    let vcf = read_vcf(&text[..]).unwrap();
+    let h = |id: u64| Handle::pack(id, false);
+
+    // AC G T A CG T AC, plus T, A and G
+    for &haplotype_paths in [false, true].iter() {
+        let VcfGraph { graph, skipped } = graph_from_vcf(&contigs, &vcf, haplotype_paths);
+        assert!(skipped.is_empty());
+        assert_eq!(graph.node_count(), 10);
+        // the alternative alleles of the two SNPs
+        assert!(graph.has_edge(h(8), h(9)));
+        // the deletion followed by the alternative allele of the last SNP
+        assert!(graph.has_edge(h(4), h(10)));
+        assert!(graph.validate().is_empty());
+    }
+}
+
+#[test]
+fn graph_from_symbolic_alleles() {
+    let contigs = vec![Contig {
+        name: "chr1".into(),
+        sequence: "AACAGTTTAA".into(),
+    }];
+    let text = b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1
+chr1\t2\tinv\tA\t<INV>\t.\t.\tEND=5\tGT\t1
+chr1\t7\tdel\tT\t<DEL>\t.\t.\tEND=9\tGT\t1
+chr1\t8\tpalindrome\tT\t<INV>\t.\t.\tEND=10\tGT\t1
+chr1\t10\tbreakend\tA\tA[chr1:2[\t.\t.\t.\tGT\t1
+";
+    let vcf = read_vcf(&text[..]).unwrap();
+    let VcfGraph { graph, skipped } = graph_from_vcf(&contigs, &vcf, true);
+    assert_eq!(skipped, vec![2, 3]);
+    assert_eq!(path_sequence(&graph, b"chr1"), BString::from("AACAGTTTAA"));
+
+    // CAG is inverted to CTG, which only differs by a single base, and
+    // the deletion removes TA after the T at position 7
+    assert_eq!(
+        path_sequence(&graph, b"s1#1#chr1"),
+        BString::from("AACTGTTA")
+    );
+}
+
+#[test]
+fn variants_from_haplotype_paths() {
+    let contigs = vec![Contig {
+        name: "chr1".into(),
+        sequence: "ACGTACGTAC".into(),
+    }];
+    let text = b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\ts1\ts2
+chr1\t3\tsnp\tG\tT\t.\t.\t.\tGT\t0|1\t1|1
+chr1\t5\tdel\tACG\tA\t.\t.\t.\tGT\t1|0\t0/1
+chr1\t9\tins\tA\tATT\t.\t.\t.\tGT\t0|0\t1|0
+";
+    let input = read_vcf(&text[..]).unwrap();
+    let VcfGraph { graph, .. } = graph_from_vcf(&contigs, &input, true);
+
+    assert!(variants_from_graph(&graph, b"chr2").is_none());
+    let vcf = variants_from_graph(&graph, b"chr1").unwrap();
+    assert_eq!(vcf.samples, vec![BString::from("s1"), BString::from("s2")]);
+
+    let records: Vec<_> = vcf
+        .records
+        .iter()
+        .map(|r| {
+            let alts: Vec<String> = r.alternatives.iter().map(|a| a.to_string()).collect();
+            (r.pos, r.id.to_string(), r.reference.to_string(), alts)
+        })
+        .collect();
+    assert_eq!(
+        records,
+        vec![
+            (
+                3,
+                ">1>3".to_string(),
+                "G".to_string(),
+                vec!["T".to_string()]
+            ),
+            (
+                5,
+                ">3>5".to_string(),
+                "ACG".to_string(),
+                vec!["A".to_string()]
+            ),
+            (
+                9,
+                ">5>6".to_string(),
+                "A".to_string(),
+                vec!["ATT".to_string()]
+            ),
+        ]
+    );
+
+    // the unphased deletion of s2 wasn't threaded into its haplotypes
+    let phased = |alleles: Vec<usize>| Genotype {
+        alleles: alleles.into_iter().map(Some).collect(),
+        phased: true,
+    };
+    assert_eq!(
+        vcf.records[0].genotypes,
+        vec![phased(vec![0, 1]), phased(vec![1, 1])]
+    );
+    assert_eq!(
+        vcf.records[1].genotypes,
+        vec![phased(vec![1, 0]), phased(vec![0, 0])]
+    );
+    assert_eq!(
+        vcf.records[2].genotypes,
+        vec![phased(vec![0, 0]), phased(vec![1, 0])]
+    );
+
+    let mut written = vec![];
+    write_vcf(&mut written, &vcf).unwrap();
+    assert!(written.starts_with(b"##fileformat=VCFv4.2\n##contig=<ID=chr1>\n"));
+    assert_eq!(read_vcf(&written[..]).unwrap(), vcf);
+}
+
+#[test]
+fn variants_skip_bubbles_the_reference_cant_follow() {
+    use handlegraph2::mutablehandlegraph::*;
+
+    // two SNPs, the reference goes 1 2 4 5 7 and s1 goes 1 3 4 6 7
+    let two_bubbles = |reference: &[u64], reverse: bool| {
+        let mut graph = HashGraph::new();
+        let seqs = [b"A", b"C", b"G", b"T", b"A", b"C", b"G"];
+        for (id, seq) in seqs.iter().enumerate() {
+            graph.create_handle(&seq[..], id as u64 + 1);
+        }
+        graph.create_handle(b"T", 99);
+        let h = |id: u64| Handle::pack(id, false);
+        let edges = [
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 5),
+            (4, 6),
+            (5, 7),
+            (6, 7),
+        ];
+        for &(l, r) in edges.iter() {
+            graph.create_edge(Edge(h(l), h(r)));
+        }
+        let path = graph.create_path_handle(b"ref", false);
+        for &id in reference.iter() {
+            let step = if reverse { h(id).flip() } else { h(id) };
+            graph.append_step(&path, step);
+        }
+        let path = graph.create_path_handle(b"s1#1#ref", false);
+        for &id in [1, 3, 4, 6, 7].iter() {
+            graph.append_step(&path, h(id));
+        }
+        graph
+    };
+    let records = |graph: &HashGraph| -> Vec<(usize, String, String)> {
+        variants_from_graph(graph, b"ref")
+            .unwrap()
+            .records
+            .iter()
+            .map(|r| (r.pos, r.id.to_string(), r.reference.to_string()))
+            .collect()
+    };
+
+    let forward = two_bubbles(&[1, 2, 4, 5, 7], false);
+    let expected = vec![
+        (2, ">1>4".to_string(), "C".to_string()),
+        (4, ">4>7".to_string(), "A".to_string()),
+    ];
+    assert_eq!(records(&forward), expected);
+
+    // the reference walks both bubbles backwards
+    let backward = two_bubbles(&[7, 5, 4, 2, 1], true);
+    assert_eq!(
+        records(&backward),
+        vec![
+            (2, ">4>7".to_string(), "T".to_string()),
+            (4, ">1>4".to_string(), "G".to_string()),
+        ]
+    );
+
+    // the reference has a step on a node that is gone after the first
+    // bubble, so the second one has no position on it
+    let mut broken = two_bubbles(&[1, 2, 4, 99, 5, 7], false);
+    broken.graph.remove(&99u64.into());
+    assert_eq!(records(&broken), expected[..1].to_vec());
+}